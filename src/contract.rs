@@ -1,30 +1,131 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
+use std::collections::{BTreeMap, HashMap, HashSet};
+
 use cosmwasm_std::{
-    coins, from_binary, to_binary, BankMsg, CosmosMsg, DepsMut, Env, MessageInfo, Response, SubMsg,
-    Uint128, WasmMsg,
+    attr, coins, from_binary, to_binary, Addr, Attribute, BankMsg, Binary, Coin, CosmosMsg,
+    Decimal, Deps, DepsMut, Env, Event, MessageInfo, Order, Reply, Response, StdError, StdResult,
+    Storage, SubMsg, SubMsgResult, Timestamp, Uint128, WasmMsg,
 };
-use cw2::set_contract_version;
-use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use schemars::schema::{Schema, SchemaObject};
+use schemars::schema_for;
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
-use crate::msg::{Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, Recipient};
+use crate::msg::{
+    default_max_recipients, AddressFormat, AllParamsResponse, ApprovalQueryMsg, ApprovalResponse,
+    AssetAmount, CanAffordResponse, CheckResponse, ClaimableBalanceResponse, ConfigResponse,
+    Cw20HookMsg, Cw4MemberListResponse, Cw4QueryMsg, DebtResponse, DecodeHookResponse, DenyPolicy,
+    DiffRecipientsResponse, EventLevel, ExecuteMsg, FailurePolicy, HasOptedInResponse,
+    IdentifiedRecipient, InstantiateMsg, IsFrozenResponse, LendingExecuteMsg, LendingQueryMsg,
+    MessageDescription, MessageSchemaResponse, MigrateMsg, MultiRecipient,
+    NormalizeWeightsResponse, OracleQueryMsg, OutputKind, PolicyQueryMsg, PostHookMsg,
+    PreferredRecipient, PriceResponse, QueryMsg, Recipient, RecipientChange,
+    RecipientTotalResponse, RegistryQueryMsg, ReleaseBucket, ReleaseForecastResponse,
+    ResolveResponse, Share, ShortfallResponse, SimOp, SimOpResult, SimulateBatchResponse,
+    StakingExecuteMsg, StorageEstimateResponse, SuggestPageSizeResponse, SwapExecuteMsg,
+    TotalEscrowedResponse, UnwrapHookMsg, ValidateBatchResponse, VaultExecuteMsg,
+    WeightedRecipient, WrapperExecuteMsg,
+};
+use crate::state::{
+    BestEffortBatch, Config, Escrow, MultiAssetBatch, PendingSwap, PendingSwapDistribution,
+    PendingUnwrap, PendingVault, PendingWrap, BEST_EFFORT_BATCH, CLAIMABLE, CONFIG, ESCROWED,
+    ESCROWS, ESCROW_COUNT, FROZEN, MULTI_ASSET_BATCH, MULTI_ASSET_REPLY_OFFSET, NONCES, OPTED_IN,
+    PENDING_SWAP, PENDING_SWAP_DISTRIBUTION, PENDING_UNWRAP, PENDING_VAULT, PENDING_WRAP,
+    RECIPIENT_TOTALS, RECLAIMABLE, RESERVED, SWAP_DISTRIBUTION_REPLY_ID, SWAP_REPLY_ID,
+    UNWRAP_REPLY_ID, VAULT_REPLY_ID, WRAP_REPLY_ID,
+};
 
 /// Contract name that is used for migration.
 const CONTRACT_NAME: &str = "nebula-airdrop";
 /// Contract version that is used for migration.
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Approximate size in bytes of a recipient total entry, key and value.
+const RECIPIENT_TOTAL_ENTRY_BYTES: u64 = 100;
+/// Approximate size in bytes of an escrow entry, key and value.
+const ESCROW_ENTRY_BYTES: u64 = 160;
+/// Maximum number of buckets of a release forecast.
+const MAX_FORECAST_BUCKETS: u32 = 1_000;
+/// Maximum horizon of a release forecast in seconds, about ten years.
+const MAX_FORECAST_HORIZON: u64 = 10 * 365 * 24 * 60 * 60;
+/// Approximate gas a native distribution uses per recipient, transfer and total update included.
+const GAS_PER_RECIPIENT: u64 = 25_000;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
-    _msg: InstantiateMsg,
+    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    // every currency needs a unique name and a rate that converts to a non-zero amount
+    let mut currencies = HashSet::new();
+    for rate in msg.currencies.iter() {
+        if rate.rate.is_zero() {
+            return Err(ContractError::Generic(format!(
+                "rate of currency {} must be greater than zero",
+                rate.currency
+            )));
+        }
+        if !currencies.insert(&rate.currency) {
+            return Err(ContractError::Generic(format!(
+                "duplicate currency {}",
+                rate.currency
+            )));
+        }
+    }
+
+    let config = Config {
+        owner: Some(deps.api.addr_validate(&msg.owner)?),
+        post_hook_contract: msg
+            .post_hook_contract
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        max_denoms_per_tx: msg.max_denoms_per_tx,
+        min_total: msg.min_total,
+        policy_contract: msg
+            .policy_contract
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        deny_policy: msg.deny_policy,
+        treasury_fallback: msg
+            .treasury_fallback
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        max_recipients: msg.max_recipients,
+        bridge_registry: msg
+            .bridge_registry
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        event_verbosity: msg.event_verbosity,
+        address_format: msg.address_format,
+        require_opt_in: msg.require_opt_in,
+        approval_hook: msg
+            .approval_hook
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        lifetime_cap: msg.lifetime_cap,
+        vault_contract: msg
+            .vault_contract
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        currencies: msg.currencies,
+        unwrap_adapter: msg
+            .unwrap_adapter
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        fallback_recipient: msg
+            .fallback_recipient
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
     Ok(Response::new())
 }
 
@@ -34,6 +135,8 @@ pub fn instantiate(
 /// ## Params
 /// - **deps** is an object of type [`DepsMut`].
 ///
+/// - **env** is an object of type [`Env`].
+///
 /// - **info** is an object of type [`MessageInfo`].
 ///
 /// - **msg** is an object of type [`ExecuteMsg`].
@@ -42,23 +145,327 @@ pub fn instantiate(
 /// - **ExecuteMsg::Receive (msg)** Receives CW20 tokens and executes a hook message.
 ///
 /// - **ExecuteMsg::DistributeNative {
-///             denom,
-///             recipients,
-///         }** Distributes native tokenss.
+///   denom,
+///   recipients,
+///   failure_policy,
+///   best_effort,
+///   gas_reserve_denom,
+///   display_decimals,
+///   nonce,
+///   }** Distributes native tokenss.
+///
+/// - **ExecuteMsg::DistributeLinear {
+///   denom,
+///   recipients,
+///   top_amount,
+///   bottom_amount,
+///   }** Distributes native tokens with linearly interpolated amounts.
+///
+/// - **ExecuteMsg::DistributeMultiAsset { recipients, atomic }** Distributes several native
+///   tokens at once.
+///
+/// - **ExecuteMsg::DistributeTimeWeighted {
+///   denom,
+///   snapshots,
+///   precision,
+///   snapshot_height,
+///   }** Distributes native tokens proportional to time-weighted balances.
+///
+/// - **ExecuteMsg::DistributePreferred { default_denom, recipients }** Distributes native tokens
+///   in each recipient's preferred denom when available.
+///
+/// - **ExecuteMsg::DistributeGroupWithReserve {
+///   denom,
+///   group_addr,
+///   reserve,
+///   reserve_to,
+///   precision,
+///   }** Sends a reserve and splits the remaining native tokens between cw4 group members.
+///
+/// - **ExecuteMsg::DistributeWrapped { denom, wrapper, recipients }** Wraps native tokens into a
+///   CW20 and distributes the wrapped tokens.
+///
+/// - **ExecuteMsg::ReclaimFailed { denom }** Returns the sender's failed best-effort and
+///   multi-asset transfers.
+///
+/// - **ExecuteMsg::DistributeConditional {
+///   denom,
+///   recipients,
+///   conditions,
+///   }** Distributes native tokens to recipients whose condition is true.
+///
+/// - **ExecuteMsg::ClaimDeferred {}** Claims all native balances deferred to the sender.
+///
+/// - **ExecuteMsg::OptIn {}** Consents to receiving distributions.
+///
+/// - **ExecuteMsg::UpdateOwner { new_owner }** Transfers ownership of the contract.
+///
+/// - **ExecuteMsg::RepayDebts {
+///   denom,
+///   lending_addr,
+///   borrowers,
+///   }** Repays borrower debts in a lending contract with the sent native tokens.
+///
+/// - **ExecuteMsg::DistributeMatched {
+///   denom,
+///   recipients,
+///   sponsor,
+///   match_bps,
+///   }** Distributes native tokens with a sponsor match on top of each base amount.
+///
+/// - **ExecuteMsg::DistributeEqualValue {
+///   denom,
+///   recipients,
+///   oracle_addr,
+///   }** Distributes native tokens in shares of equal oracle-priced value.
+///
+/// - **ExecuteMsg::DistributeNativeByShares { denom, shares }** Distributes native tokens split
+///   by fixed shares.
+///
+/// - **ExecuteMsg::DistributeRandom {
+///   denom,
+///   candidates,
+///   winners,
+///   seed,
+///   }** Distributes native tokens equally between randomly drawn winners.
+///
+/// - **ExecuteMsg::DistributeNativeEqual { denom, recipients }** Distributes native tokens
+///   equally between the recipients.
+///
+/// - **ExecuteMsg::DistributeByIdentifier {
+///   denom,
+///   recipients,
+///   failure_policy,
+///   }** Distributes native tokens to recipients resolved through the bridge registry.
+///
+/// - **ExecuteMsg::DistributeWithClawback {
+///   denom,
+///   recipients,
+///   dispute_window,
+///   }** Escrows native tokens per recipient for a dispute window.
+///
+/// - **ExecuteMsg::ClaimAfterWindow { id }** Claims an escrow after its dispute window.
+///
+/// - **ExecuteMsg::Clawback { id }** Returns an escrow to the owner during its dispute window.
+///
+/// - **ExecuteMsg::DistributeBalanceShare {
+///   denom,
+///   recipients,
+///   share_bps,
+///   }** Distributes a share of the contract's free balance by weight.
+///
+/// - **ExecuteMsg::CleanRecipients { recipients }** Emits the validated and deduplicated
+///   recipient list.
+///
+/// - **ExecuteMsg::DistributeByDiff {
+///   reward_denom,
+///   start_balances,
+///   end_balances,
+///   }** Distributes native tokens proportional to balance increases.
+///
+/// - **ExecuteMsg::SweepDustToAsset {
+///   assets,
+///   target_denom,
+///   swap_contract,
+///   }** Swaps the free balance of several denoms into one and sends it to the owner.
+///
+/// - **ExecuteMsg::DistributeFromBalance { denom, recipients }** Distributes native coins from
+///   the contract's free balance, withdrawing the shortfall from the vault contract.
+///
+/// - **ExecuteMsg::FreezeRecipient { address }** Blocks an address from receiving distributions.
+///
+/// - **ExecuteMsg::UnfreezeRecipient { address }** Allows a frozen address to receive
+///   distributions again.
+///
+/// - **ExecuteMsg::DistributeInCurrency { currency, recipients }** Distributes native tokens to
+///   recipients paid in a logical currency.
+///
+/// - **ExecuteMsg::DistributeQuadratic { denom, contributions }** Distributes a native matching
+///   pool by quadratic funding.
+///
+/// - **ExecuteMsg::DistributeSwapped {
+///   denom,
+///   recipients,
+///   swap_contract,
+///   min_output,
+///   }** Swaps the sent coins into one denom and distributes the proceeds by weight.
+///
+/// - **ExecuteMsg::DistributeWithCompound {
+///   denom,
+///   recipients,
+///   compound_bps,
+///   staking_contract,
+///   }** Distributes native tokens, staking a share of every amount for the recipient.
 ///
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Receive(msg) => receive_cw20(deps, info, msg),
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::DistributeNative {
             denom,
             recipients,
-        } => try_distribute_native(deps, info, denom, recipients),
+            failure_policy,
+            best_effort,
+            gas_reserve_denom,
+            display_decimals,
+            nonce,
+        } => {
+            use_nonce(deps.storage, &info.sender, nonce)?;
+            let (info, refund_msg) = take_gas_reserve(info, &denom, gas_reserve_denom)?;
+            let response = try_distribute_native(
+                deps,
+                info,
+                denom,
+                recipients,
+                failure_policy,
+                best_effort,
+                display_decimals,
+            )?;
+            Ok(response.add_messages(refund_msg))
+        }
+        ExecuteMsg::DistributeLinear {
+            denom,
+            recipients,
+            top_amount,
+            bottom_amount,
+        } => try_distribute_linear(deps, info, denom, recipients, top_amount, bottom_amount),
+        ExecuteMsg::DistributeMultiAsset { recipients, atomic } => {
+            try_distribute_multi_asset(deps, info, recipients, atomic)
+        }
+        ExecuteMsg::DistributeTimeWeighted {
+            denom,
+            snapshots,
+            precision,
+            snapshot_height,
+        } => try_distribute_time_weighted(deps, info, denom, snapshots, precision, snapshot_height),
+        ExecuteMsg::DistributePreferred {
+            default_denom,
+            recipients,
+        } => try_distribute_preferred(deps, info, default_denom, recipients),
+        ExecuteMsg::DistributeGroupWithReserve {
+            denom,
+            group_addr,
+            reserve,
+            reserve_to,
+            precision,
+        } => try_distribute_group_with_reserve(
+            deps, info, denom, group_addr, reserve, reserve_to, precision,
+        ),
+        ExecuteMsg::DistributeWrapped {
+            denom,
+            wrapper,
+            recipients,
+        } => try_distribute_wrapped(deps, env, info, denom, wrapper, recipients),
+        ExecuteMsg::ReclaimFailed { denom } => try_reclaim_failed(deps, info, denom),
+        ExecuteMsg::DistributeConditional {
+            denom,
+            recipients,
+            conditions,
+        } => try_distribute_conditional(deps, info, denom, recipients, conditions),
+        ExecuteMsg::ClaimDeferred {} => try_claim_deferred(deps, info),
+        ExecuteMsg::OptIn {} => try_opt_in(deps, info),
+        ExecuteMsg::UpdateOwner { new_owner } => try_update_owner(deps, info, new_owner),
+        ExecuteMsg::RepayDebts {
+            denom,
+            lending_addr,
+            borrowers,
+        } => try_repay_debts(deps, info, denom, lending_addr, borrowers),
+        ExecuteMsg::DistributeMatched {
+            denom,
+            recipients,
+            sponsor,
+            match_bps,
+        } => try_distribute_matched(deps, info, denom, recipients, sponsor, match_bps),
+        ExecuteMsg::DistributeEqualValue {
+            denom,
+            recipients,
+            oracle_addr,
+        } => try_distribute_equal_value(deps, info, denom, recipients, oracle_addr),
+        ExecuteMsg::DistributeNativeByShares { denom, shares } => {
+            try_distribute_native_by_shares(deps, info, denom, shares)
+        }
+        ExecuteMsg::DistributeRandom {
+            denom,
+            candidates,
+            winners,
+            seed,
+        } => try_distribute_random(deps, env, info, denom, candidates, winners, seed),
+        ExecuteMsg::DistributeNativeEqual { denom, recipients } => {
+            try_distribute_native_equal(deps, info, denom, recipients)
+        }
+        ExecuteMsg::DistributeByIdentifier {
+            denom,
+            recipients,
+            failure_policy,
+        } => try_distribute_by_identifier(deps, info, denom, recipients, failure_policy),
+        ExecuteMsg::DistributeWithClawback {
+            denom,
+            recipients,
+            dispute_window,
+        } => try_distribute_with_clawback(deps, env, info, denom, recipients, dispute_window),
+        ExecuteMsg::ClaimAfterWindow { id } => try_claim_after_window(deps, env, info, id),
+        ExecuteMsg::Clawback { id } => try_clawback(deps, env, info, id),
+        ExecuteMsg::DistributeBalanceShare {
+            denom,
+            recipients,
+            share_bps,
+        } => try_distribute_balance_share(deps, env, info, denom, recipients, share_bps),
+        ExecuteMsg::CleanRecipients { recipients } => try_clean_recipients(deps, recipients),
+        ExecuteMsg::DistributeByDiff {
+            reward_denom,
+            start_balances,
+            end_balances,
+        } => try_distribute_by_diff(deps, info, reward_denom, start_balances, end_balances),
+        ExecuteMsg::SweepDustToAsset {
+            assets,
+            target_denom,
+            swap_contract,
+        } => try_sweep_dust_to_asset(deps, env, info, assets, target_denom, swap_contract),
+        ExecuteMsg::DistributeFromBalance { denom, recipients } => {
+            try_distribute_from_balance(deps, env, info, denom, recipients)
+        }
+        ExecuteMsg::FreezeRecipient { address } => try_set_frozen(deps, info, address, true),
+        ExecuteMsg::UnfreezeRecipient { address } => try_set_frozen(deps, info, address, false),
+        ExecuteMsg::DistributeInCurrency {
+            currency,
+            recipients,
+        } => try_distribute_in_currency(deps, info, currency, recipients),
+        ExecuteMsg::DistributeQuadratic {
+            denom,
+            contributions,
+        } => try_distribute_quadratic(deps, info, denom, contributions),
+        ExecuteMsg::DistributeSwapped {
+            denom,
+            recipients,
+            swap_contract,
+            min_output,
+        } => try_distribute_swapped(
+            deps,
+            env,
+            info,
+            denom,
+            recipients,
+            swap_contract,
+            min_output,
+        ),
+        ExecuteMsg::DistributeWithCompound {
+            denom,
+            recipients,
+            compound_bps,
+            staking_contract,
+        } => try_distribute_with_compound(
+            deps,
+            info,
+            denom,
+            recipients,
+            compound_bps,
+            staking_contract,
+        ),
     }
 }
 
@@ -69,11 +476,14 @@ pub fn execute(
 /// ## Params
 /// - **deps** is an object of type [`DepsMut`].
 ///
+/// - **env** is an object of type [`Env`].
+///
 /// - **info** is an object of type [`MessageInfo`].
 ///
 /// - **cw20_msg** is an object of type [`Cw20ReceiveMsg`] which is a hook message to be executed.
 pub fn receive_cw20(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     cw20_msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
@@ -81,11 +491,73 @@ pub fn receive_cw20(
         Ok(Cw20HookMsg::DistributeCw20 {
             asset_token,
             recipients,
+            failure_policy,
+            nonce,
+            output,
+        }) => {
+            if info.sender != asset_token {
+                return Err(ContractError::MismatchedAssetType {});
+            };
+            let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            assert_owner(deps.as_ref(), &sender)?;
+            use_nonce(deps.storage, &sender, nonce)?;
+            match output {
+                OutputKind::SameToken => try_distribute_cw20(
+                    deps,
+                    cw20_msg.amount,
+                    asset_token,
+                    recipients,
+                    failure_policy,
+                ),
+                OutputKind::Native(denom) => try_distribute_unwrapped(
+                    deps,
+                    env,
+                    sender,
+                    AssetAmount {
+                        asset: asset_token,
+                        amount: cw20_msg.amount,
+                    },
+                    denom,
+                    recipients,
+                    failure_policy,
+                ),
+            }
+        }
+        Ok(Cw20HookMsg::DistributeCw20ByShares {
+            asset_token,
+            shares,
+        }) => {
+            if info.sender != asset_token {
+                return Err(ContractError::MismatchedAssetType {});
+            };
+            let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            assert_owner(deps.as_ref(), &sender)?;
+            let recipients = split_by_weight(cw20_msg.amount, share_weights(shares), None)?;
+            try_distribute_cw20(
+                deps,
+                cw20_msg.amount,
+                asset_token,
+                recipients,
+                FailurePolicy::AbortAll,
+            )
+        }
+        Ok(Cw20HookMsg::DistributeCw20Equal {
+            asset_token,
+            recipients,
         }) => {
-            if info.sender.to_string() != asset_token {
+            if info.sender != asset_token {
                 return Err(ContractError::MismatchedAssetType {});
             };
-            try_distribute_cw20(deps, cw20_msg.amount, asset_token, recipients)
+            let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            assert_owner(deps.as_ref(), &sender)?;
+            let recipients = split_equally(cw20_msg.amount, recipients)?;
+            try_distribute_cw20(
+                deps,
+                cw20_msg.amount,
+                asset_token,
+                recipients,
+                FailurePolicy::AbortAll,
+            )
         }
         Err(_) => Err(ContractError::Generic("invalid cw20 hook message".to_string())),
     }
@@ -102,12 +574,25 @@ pub fn receive_cw20(
 /// - **asset_token** is an object of type [`String`] which is the contract address of the CW20 token to distribute.
 ///
 /// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount to distribute to.
+///
+/// - **failure_policy** is an object of type [`FailurePolicy`] which decides how invalid recipient addresses are handled.
 pub fn try_distribute_cw20(
     deps: DepsMut,
     amount: Uint128,
     asset_token: String,
     recipients: Vec<Recipient>,
+    failure_policy: FailurePolicy,
 ) -> Result<Response, ContractError> {
+    validate_recipient_amounts(&recipients)?;
+    if recipients.iter().any(|recipient| recipient.deferred) {
+        return Err(ContractError::UnsupportedDeferred {});
+    }
+    let FilteredRecipients {
+        kept: recipients,
+        skipped,
+        redirected,
+    } = filter_recipients(deps.as_ref(), recipients, &failure_policy)?;
+
     // validate sent coin amount matches sum(recipient amounts)
     let sum_recipient_amount: Uint128 =
         recipients.iter().fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
@@ -116,16 +601,41 @@ pub fn try_distribute_cw20(
     }
 
     // check for duplicate recipient address
-    if (1..recipients.len()).any(|i| recipients[i..].contains(&recipients[i - 1])) {
+    if has_duplicate_address(
+        recipients
+            .iter()
+            .map(|recipient| recipient.recipient.as_str()),
+    ) {
         return Err(ContractError::DuplicateRecipient {});
     }
 
-    // construct transfer messsage vector
-    let mut transfer_msgs: Vec<SubMsg> = vec![];
+    // bound the number of transfers built in one call
+    assert_max_recipients(deps.as_ref(), recipients.len())?;
+    assert_approved(deps.as_ref(), &asset_token, &recipients)?;
+
+    // construct transfer messsage vector, one message per recipient as neither `BankMsg::Send`
+    // nor CW20 `Transfer` can pay several recipients at once. No reply is needed, so plain
+    // messages are used instead of submessages.
+    let mut transfer_msgs: Vec<CosmosMsg> = vec![];
+    let mut events: Vec<Event> = vec![];
+    assert_opted_in(deps.as_ref(), &recipients)?;
     for recipient in recipients.iter() {
-        deps.api.addr_validate(&recipient.recipient)?;
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+        assert_lifetime_cap(
+            deps.as_ref(),
+            &recipient_addr,
+            &asset_token,
+            recipient.amount,
+        )?;
+        add_recipient_total(
+            deps.storage,
+            &recipient_addr,
+            &asset_token,
+            recipient.amount,
+        )?;
+        events.push(recipient_event("distribute_transfer", recipient));
 
-        transfer_msgs.push(SubMsg::new(WasmMsg::Execute {
+        transfer_msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: asset_token.to_string(),
             funds: vec![],
             msg: to_binary(&Cw20ExecuteMsg::Transfer {
@@ -135,7 +645,111 @@ pub fn try_distribute_cw20(
             .unwrap(),
         }))
     }
-    Ok(Response::new().add_submessages(transfer_msgs))
+
+    let event_verbosity = CONFIG.load(deps.storage)?.event_verbosity;
+    let mut response = Response::new()
+        .add_messages(transfer_msgs)
+        .add_attribute("action", "distribute_cw20");
+    if event_verbosity != EventLevel::Minimal {
+        response = response
+            .add_attribute("asset_token", asset_token)
+            .add_attribute("total_amount", sum_recipient_amount)
+            .add_attribute("num_recipients", recipients.len().to_string());
+        if !skipped.is_empty() {
+            response = response.add_attribute("skipped", skipped.join(","));
+        }
+        if !redirected.is_empty() {
+            response = response.add_attribute("redirected", redirected.join(","));
+        }
+    }
+    if event_verbosity == EventLevel::Full {
+        response = response.add_events(events);
+    }
+    Ok(response)
+}
+
+/// ## Description
+/// Handles distribution of CW20 tokens paid out as native coins. The recipients are checked like
+/// a native distribution of `denom`, then the tokens are sent to the configured unwrap adapter
+/// with `UNWRAP_REPLY_ID` and the recipients are paid once it replies, see [`reply_unwrap`].
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **sender** is an object of type [`Addr`] which is the address that sent the tokens.
+///
+/// - **received** is an object of type [`AssetAmount`] which is the received CW20 token and amount.
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to pay.
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount to distribute to.
+///
+/// - **failure_policy** is an object of type [`FailurePolicy`] which decides how invalid recipient addresses are handled.
+pub fn try_distribute_unwrapped(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    received: AssetAmount,
+    denom: String,
+    recipients: Vec<Recipient>,
+    failure_policy: FailurePolicy,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let adapter = config
+        .unwrap_adapter
+        .ok_or_else(|| ContractError::Generic("no unwrap adapter configured".to_string()))?;
+    let total = check_batch_recipients(
+        deps.as_ref(),
+        &denom,
+        recipients.clone(),
+        &failure_policy,
+        true,
+    )?;
+    if received.amount != total {
+        return Err(ContractError::MismatchedAssetAmount {});
+    }
+    check_min_total(deps.as_ref(), total)?;
+
+    let balance_before = deps
+        .querier
+        .query_balance(&env.contract.address, &denom)?
+        .amount;
+    PENDING_UNWRAP.save(
+        deps.storage,
+        &PendingUnwrap {
+            sender,
+            denom: denom.clone(),
+            amount: received.amount,
+            balance_before,
+            recipients,
+            failure_policy,
+        },
+    )?;
+    let unwrap_msg = WasmMsg::Execute {
+        contract_addr: received.asset.clone(),
+        msg: to_binary(&Cw20ExecuteMsg::Send {
+            contract: adapter.to_string(),
+            amount: received.amount,
+            msg: to_binary(&UnwrapHookMsg::Unwrap {
+                denom: denom.clone(),
+            })?,
+        })?,
+        funds: vec![],
+    };
+
+    Ok(add_details(
+        &config.event_verbosity,
+        Response::new()
+            .add_submessage(SubMsg::reply_on_success(unwrap_msg, UNWRAP_REPLY_ID))
+            .add_attribute("action", "distribute_cw20_unwrapped"),
+        vec![
+            attr("asset_token", received.asset),
+            attr("denom", denom),
+            attr("total_amount", received.amount),
+        ],
+    ))
 }
 
 /// ## Description
@@ -149,21 +763,31 @@ pub fn try_distribute_cw20(
 /// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
 ///
 /// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount to distribute to.
+///
+/// - **failure_policy** is an object of type [`FailurePolicy`] which decides how invalid recipient addresses are handled.
+///
+/// - **best_effort** is an object of type [`bool`] which keeps distributing to the other recipients if a transfer fails.
+///
+/// - **display_decimals** is an [`Option`] of type [`u8`] which adds a `display_amount` event per paid recipient when set.
 pub fn try_distribute_native(
     deps: DepsMut,
     info: MessageInfo,
     denom: String,
     recipients: Vec<Recipient>,
+    failure_policy: FailurePolicy,
+    best_effort: bool,
+    display_decimals: Option<u8>,
 ) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    validate_recipient_amounts(&recipients)?;
+    let FilteredRecipients {
+        kept: recipients,
+        skipped,
+        redirected,
+    } = filter_recipients(deps.as_ref(), recipients, &failure_policy)?;
+
     // validate sent coin denom
-    let mut amount = Uint128::zero();
-    for coin in info.funds.iter() {
-        if coin.denom != denom {
-            return Err(ContractError::MismatchedAssetType {});
-        } else {
-            amount = coin.amount;
-        }
-    }
+    let amount = sent_native_amount(&info, &denom)?;
     let sum_recipient_amount: Uint128 =
         recipients.iter().fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
 
@@ -172,35 +796,8890 @@ pub fn try_distribute_native(
         return Err(ContractError::MismatchedAssetAmount {});
     }
 
+    // reject dust-sized distributions
+    check_min_total(deps.as_ref(), sum_recipient_amount)?;
+
     // check for duplicate recipient address
-    if (1..recipients.len()).any(|i| recipients[i..].contains(&recipients[i - 1])) {
+    if has_duplicate_address(
+        recipients
+            .iter()
+            .map(|recipient| recipient.recipient.as_str()),
+    ) {
         return Err(ContractError::DuplicateRecipient {});
     }
 
-    // construct transfer messsage vector
-    let mut transfer_msgs: Vec<SubMsg> = vec![];
-    for recipient in recipients.iter() {
-        deps.api.addr_validate(&recipient.recipient)?;
+    // bound the number of transfers built in one call
+    assert_max_recipients(deps.as_ref(), recipients.len())?;
+    assert_approved(deps.as_ref(), &denom, &recipients)?;
 
-        transfer_msgs.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+    // construct transfer messsage vector, recording a claimable balance for deferred recipients
+    let mut sent: Vec<Recipient> = vec![];
+    let mut transfer_msgs: Vec<CosmosMsg> = vec![];
+    let mut events: Vec<Event> = vec![];
+    let require_opt_in = CONFIG.load(deps.storage)?.require_opt_in;
+    for recipient in recipients.into_iter() {
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+        assert_lifetime_cap(deps.as_ref(), &recipient_addr, &denom, recipient.amount)?;
+        // recipients that did not opt in are offered a claimable balance instead
+        if recipient.deferred || (require_opt_in && !has_opted_in(deps.storage, &recipient_addr)) {
+            add_claimable(deps.storage, &recipient_addr, &denom, recipient.amount)?;
+            events.push(recipient_event("distribute_deferred", &recipient));
+            continue;
+        }
+        add_recipient_total(deps.storage, &recipient_addr, &denom, recipient.amount)?;
+        events.push(recipient_event("distribute_transfer", &recipient));
+
+        transfer_msgs.push(CosmosMsg::Bank(BankMsg::Send {
             to_address: recipient.recipient.clone(),
             amount: coins(recipient.amount.into(), denom.clone()),
-        })))
+        }));
+        sent.push(recipient);
     }
 
-    Ok(Response::new().add_submessages(transfer_msgs))
+    // the hook and the display amounts only cover the recipients paid right away
+    let num_recipients = sent.len() as u32;
+    let sent_amount: Uint128 = sent
+        .iter()
+        .fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
+    let display_events = match display_decimals {
+        Some(decimals) => display_amount_events(&sent, decimals),
+        None => vec![],
+    };
+    let mut response = if best_effort {
+        // every transfer replies on error so a failing recipient does not revert the others,
+        // see `reply`
+        BEST_EFFORT_BATCH.save(
+            deps.storage,
+            &BestEffortBatch {
+                sender: info.sender.clone(),
+                denom: denom.clone(),
+                recipients: sent,
+            },
+        )?;
+        Response::new().add_submessages(
+            transfer_msgs
+                .into_iter()
+                .enumerate()
+                .map(|(i, msg)| SubMsg::reply_on_error(msg, i as u64)),
+        )
+    } else {
+        Response::new().add_messages(transfer_msgs)
+    };
+    let event_verbosity = CONFIG.load(deps.storage)?.event_verbosity;
+    response = response.add_attribute("action", "distribute_native");
+    if event_verbosity != EventLevel::Minimal {
+        response = response
+            .add_attribute("denom", &denom)
+            .add_attribute("total_amount", sum_recipient_amount)
+            .add_attribute("num_recipients", events.len().to_string());
+        if !skipped.is_empty() {
+            response = response.add_attribute("skipped", skipped.join(","));
+        }
+        if !redirected.is_empty() {
+            response = response.add_attribute("redirected", redirected.join(","));
+        }
+    }
+    if event_verbosity == EventLevel::Full {
+        response = response.add_events(events).add_events(display_events);
+    }
+    if let Some(hook_msg) = post_hook_msg(
+        deps.as_ref(),
+        &info,
+        coins(sent_amount.u128(), denom),
+        num_recipients,
+    )? {
+        response = response.add_message(hook_msg);
+    }
+    Ok(response)
 }
 
 /// ## Description
-/// Exposes the migrate functionality in the contract.
+/// Handles distribution of native Cosmos SDK coins where each recipient's amount is interpolated
+/// linearly between `top_amount` for the first recipient and `bottom_amount` for the last one.
+/// Interpolated amounts are truncated towards `top_amount`, so no dust is produced and the sent
+/// amount must equal the sum of the computed amounts.
 ///
 /// ## Params
-/// - **_deps** is an object of type [`DepsMut`].
+/// - **deps** is an object of type [`DepsMut`].
 ///
-/// - **_env** is an object of type [`Env`].
+/// - **info** is an object of type [`MessageInfo`].
 ///
-/// - **_msg** is an object of type [`MigrateMsg`].
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<String>`] which is the ordered list of recipient addresses.
+///
+/// - **top_amount** is an object of type [`Uint128`] which is the amount the first recipient receives.
+///
+/// - **bottom_amount** is an object of type [`Uint128`] which is the amount the last recipient receives.
+pub fn try_distribute_linear(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<String>,
+    top_amount: Uint128,
+    bottom_amount: Uint128,
+) -> Result<Response, ContractError> {
+    let recipients = linear_amounts(recipients, top_amount, bottom_amount);
+
+    try_distribute_native(
+        deps,
+        info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )
+}
+
+/// ## Description
+/// Interpolates each recipient's amount linearly between `top_amount` for the first recipient
+/// and `bottom_amount` for the last one, see [`try_distribute_linear`].
+///
+/// ## Params
+/// - **recipients** is an object of type [`Vec<String>`] which is the ordered list of recipient addresses.
+///
+/// - **top_amount** is an object of type [`Uint128`] which is the amount the first recipient receives.
+///
+/// - **bottom_amount** is an object of type [`Uint128`] which is the amount the last recipient receives.
+fn linear_amounts(
+    recipients: Vec<String>,
+    top_amount: Uint128,
+    bottom_amount: Uint128,
+) -> Vec<Recipient> {
+    let steps = Uint128::from(recipients.len().saturating_sub(1) as u128);
+    recipients
+        .into_iter()
+        .enumerate()
+        .map(|(i, recipient)| {
+            let amount = if steps.is_zero() {
+                top_amount
+            } else if top_amount >= bottom_amount {
+                top_amount - (top_amount - bottom_amount).multiply_ratio(i as u128, steps)
+            } else {
+                top_amount + (bottom_amount - top_amount).multiply_ratio(i as u128, steps)
+            };
+            Recipient {
+                recipient,
+                amount,
+                deferred: false,
+            }
+        })
+        .collect()
+}
+
+/// ## Description
+/// Handles distribution of several native Cosmos SDK coins at once. When `atomic`, each
+/// recipient receives all of their coins in a single bank transfer and any failure reverts the
+/// whole distribution. Otherwise every coin is sent in its own transfer that always replies, so
+/// a failing asset does not block the others and each transfer reports whether it succeeded,
+/// see `reply`. Recipients are screened like those of a native distribution, see
+/// [`filter_multi_recipients`]. If opt-in is required, the coins of recipients that did not opt
+/// in are deferred until they claim them.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **recipients** is an object of type [`Vec<MultiRecipient>`] which is the list of recipient address and coins to distribute to.
+///
+/// - **atomic** is an object of type [`bool`] which reverts every transfer if one fails.
+pub fn try_distribute_multi_asset(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipients: Vec<MultiRecipient>,
+    atomic: bool,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    let FilteredRecipients {
+        kept: recipients,
+        skipped,
+        redirected,
+    } = filter_multi_recipients(deps.as_ref(), recipients)?;
+
+    // validate sent coins match the aggregate of all recipient coins
+    let sent = aggregate_coins(info.funds.iter());
+    let required = aggregate_coins(
+        recipients
+            .iter()
+            .flat_map(|recipient| recipient.coins.iter()),
+    );
+    if !sent.keys().eq(required.keys()) {
+        return Err(ContractError::MismatchedAssetType {});
+    }
+    if sent != required {
+        return Err(ContractError::MismatchedAssetAmount {});
+    }
+
+    // bound the number of denoms sent in one distribution
+    if let Some(limit) = CONFIG.load(deps.storage)?.max_denoms_per_tx {
+        let got = required.len() as u32;
+        if got > limit {
+            return Err(ContractError::TooManyDenoms { limit, got });
+        }
+    }
+
+    // check for duplicate recipient address
+    if has_duplicate_address(
+        recipients
+            .iter()
+            .map(|recipient| recipient.recipient.as_str()),
+    ) {
+        return Err(ContractError::DuplicateRecipient {});
+    }
+
+    // bound the number of transfers built in one call
+    assert_max_recipients(deps.as_ref(), recipients.len())?;
+
+    assert_multi_asset_approved(deps.as_ref(), &recipients)?;
+
+    // construct transfer messsage vector
+    let mut transfer_msgs: Vec<CosmosMsg> = vec![];
+    let mut transfers: Vec<(Addr, Coin)> = vec![];
+    let mut num_recipients = 0u32;
+    let mut paid = required;
+    let require_opt_in = CONFIG.load(deps.storage)?.require_opt_in;
+    for recipient in recipients.iter() {
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+
+        let amount: Vec<Coin> = aggregate_coins(recipient.coins.iter())
+            .into_iter()
+            .map(|(denom, amount)| Coin { denom, amount })
+            .collect();
+        if amount.is_empty() {
+            continue;
+        }
+        for coin in amount.iter() {
+            assert_lifetime_cap(deps.as_ref(), &recipient_addr, &coin.denom, coin.amount)?;
+        }
+        // recipients that did not opt in are offered a claimable balance instead
+        if require_opt_in && !has_opted_in(deps.storage, &recipient_addr) {
+            for coin in amount {
+                add_claimable(deps.storage, &recipient_addr, &coin.denom, coin.amount)?;
+                paid.insert(coin.denom.clone(), paid[&coin.denom] - coin.amount);
+            }
+            continue;
+        }
+        num_recipients += 1;
+        for coin in amount.iter() {
+            add_recipient_total(deps.storage, &recipient_addr, &coin.denom, coin.amount)?;
+        }
+        if atomic {
+            transfer_msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.recipient.clone(),
+                amount,
+            }))
+        } else {
+            for coin in amount {
+                transfer_msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: recipient.recipient.clone(),
+                    amount: vec![coin.clone()],
+                }));
+                transfers.push((recipient_addr.clone(), coin));
+            }
+        }
+    }
+
+    // the hook only covers the coins paid right away
+    let amount = paid
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(denom, amount)| Coin { denom, amount })
+        .collect();
+    let mut response = if atomic {
+        Response::new().add_messages(transfer_msgs)
+    } else {
+        MULTI_ASSET_BATCH.save(
+            deps.storage,
+            &MultiAssetBatch {
+                sender: info.sender.clone(),
+                transfers,
+            },
+        )?;
+        Response::new().add_submessages(
+            transfer_msgs
+                .into_iter()
+                .enumerate()
+                .map(|(i, msg)| SubMsg::reply_always(msg, MULTI_ASSET_REPLY_OFFSET + i as u64)),
+        )
+    };
+    if let Some(hook_msg) = post_hook_msg(deps.as_ref(), &info, amount, num_recipients)? {
+        response = response.add_message(hook_msg);
+    }
+    let mut details = vec![attr("atomic", atomic.to_string())];
+    if !skipped.is_empty() {
+        details.push(attr("skipped", skipped.join(",")));
+    }
+    if !redirected.is_empty() {
+        details.push(attr("redirected", redirected.join(",")));
+    }
+    Ok(add_details(
+        &CONFIG.load(deps.storage)?.event_verbosity,
+        response.add_attribute("action", "distribute_multi_asset"),
+        details,
+    ))
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins proportional to each address's time-weighted
+/// balance, e.g. for liquidity mining rewards. See [`split_by_weight`] for the dust handling.
+/// The snapshot height is not verified on-chain; it is only emitted for auditors to check the
+/// snapshots against chain history.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **snapshots** is an object of type [`Vec<(String, Uint128)>`] which is the list of addresses and their time-weighted balance.
+///
+/// - **precision** is an [`Option`] of type [`Uint128`] which is the multiple each payout is rounded down to.
+///
+/// - **snapshot_height** is an [`Option`] of type [`u64`] which is the block height the caller claims the snapshots are from.
+pub fn try_distribute_time_weighted(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    snapshots: Vec<(String, Uint128)>,
+    precision: Option<Uint128>,
+    snapshot_height: Option<u64>,
+) -> Result<Response, ContractError> {
+    let event_verbosity = CONFIG.load(deps.storage)?.event_verbosity;
+    let amount = sent_native_amount(&info, &denom)?;
+    let recipients = split_by_weight(amount, snapshots, precision)?;
+
+    let mut response = try_distribute_native(
+        deps,
+        info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )?;
+    if let Some(snapshot_height) = snapshot_height {
+        response = add_details(
+            &event_verbosity,
+            response,
+            vec![attr("snapshot_height", snapshot_height.to_string())],
+        );
+    }
+    Ok(response)
+}
+
+/// ## Description
+/// Handles distribution of several native Cosmos SDK coins where each recipient is paid in their
+/// preferred denom if enough of it is left from the sent coins, and in `default_denom` otherwise.
+/// Recipients are resolved in order and the coins left over once all are paid are refunded to
+/// the sender, including the amounts of recipients skipped by [`screen_recipient`]. If opt-in is
+/// required, recipients that did not opt in are offered a claimable balance in their resolved
+/// denom instead.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **default_denom** is an object of type [`String`] which is the denomination paying recipients whose preference is unavailable.
+///
+/// - **recipients** is an object of type [`Vec<PreferredRecipient>`] which is the list of recipient address, amount and preferred denom.
+pub fn try_distribute_preferred(
+    deps: DepsMut,
+    info: MessageInfo,
+    default_denom: String,
+    recipients: Vec<PreferredRecipient>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    if recipients.is_empty() {
+        return Err(ContractError::EmptyRecipients {});
+    }
+    // check for duplicate recipient address
+    if has_duplicate_address(
+        recipients
+            .iter()
+            .map(|recipient| recipient.recipient.as_str()),
+    ) {
+        return Err(ContractError::DuplicateRecipient {});
+    }
+
+    // bound the number of transfers built in one call
+    assert_max_recipients(deps.as_ref(), recipients.len())?;
+
+    // resolve every recipient's denom against what is left of the sent coins
+    let config = CONFIG.load(deps.storage)?;
+    let mut available = aggregate_coins(info.funds.iter());
+    let mut paid: BTreeMap<String, Uint128> = BTreeMap::new();
+    let mut plans: BTreeMap<String, Vec<Recipient>> = BTreeMap::new();
+    let mut skipped: Vec<String> = vec![];
+    let mut redirected: Vec<String> = vec![];
+    let mut transfer_msgs: Vec<CosmosMsg> = vec![];
+    for mut recipient in recipients.into_iter() {
+        match screen_recipient(
+            deps.as_ref(),
+            &config,
+            &recipient.recipient,
+            &FailurePolicy::AbortAll,
+        )? {
+            Screening::Keep => {}
+            Screening::Skip => {
+                skipped.push(recipient.recipient);
+                continue;
+            }
+            Screening::Redirect(fallback) => {
+                redirected.push(format!("{}:{}", recipient.recipient, recipient.amount));
+                recipient.recipient = fallback.to_string();
+            }
+        }
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+
+        let denom = match recipient.preferred_denom {
+            Some(preferred)
+                if available.get(&preferred).copied().unwrap_or_default() >= recipient.amount =>
+            {
+                preferred
+            }
+            _ => default_denom.clone(),
+        };
+        let left = available
+            .get(&denom)
+            .copied()
+            .unwrap_or_default()
+            .checked_sub(recipient.amount)
+            .map_err(|_| ContractError::InsufficientFunds {
+                denom: denom.clone(),
+            })?;
+        available.insert(denom.clone(), left);
+        plans.entry(denom.clone()).or_default().push(Recipient {
+            recipient: recipient.recipient.clone(),
+            amount: recipient.amount,
+            deferred: false,
+        });
+        assert_lifetime_cap(deps.as_ref(), &recipient_addr, &denom, recipient.amount)?;
+        // recipients that did not opt in are offered a claimable balance instead
+        if config.require_opt_in && !has_opted_in(deps.storage, &recipient_addr) {
+            add_claimable(deps.storage, &recipient_addr, &denom, recipient.amount)?;
+            continue;
+        }
+        add_recipient_total(deps.storage, &recipient_addr, &denom, recipient.amount)?;
+        *paid.entry(denom.clone()).or_default() += recipient.amount;
+
+        transfer_msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.recipient,
+            amount: coins(recipient.amount.u128(), denom),
+        }))
+    }
+
+    // every resolved denom is approved as its own plan
+    for (denom, plan) in plans.iter() {
+        assert_approved(deps.as_ref(), denom, plan)?;
+    }
+
+    // the hook only covers the coins paid right away
+    let paid: Vec<Coin> = paid
+        .into_iter()
+        .map(|(denom, amount)| Coin { denom, amount })
+        .collect();
+    let num_recipients = transfer_msgs.len() as u32;
+    let mut response = Response::new().add_messages(transfer_msgs);
+    if let Some(hook_msg) = post_hook_msg(deps.as_ref(), &info, paid, num_recipients)? {
+        response = response.add_message(hook_msg);
+    }
+    let refund: Vec<Coin> = available
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(denom, amount)| Coin { denom, amount })
+        .collect();
+    if !refund.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund,
+        });
+    }
+    let mut details = vec![];
+    if !skipped.is_empty() {
+        details.push(attr("skipped", skipped.join(",")));
+    }
+    if !redirected.is_empty() {
+        details.push(attr("redirected", redirected.join(",")));
+    }
+    Ok(add_details(&config.event_verbosity, response, details))
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins to a cw4 group. The `reserve` is sent to
+/// `reserve_to` first and the remainder is split between the group members by weight. Members
+/// with zero weight are ignored. If `reserve_to` is a member itself, the reserve is added to
+/// its share. See [`split_by_weight`] for the dust handling.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **group_addr** is an object of type [`String`] which is the address of the cw4 group contract.
+///
+/// - **reserve** is an object of type [`Uint128`] which is the amount sent to `reserve_to`.
+///
+/// - **reserve_to** is an object of type [`String`] which is the address receiving the reserve.
+///
+/// - **precision** is an [`Option`] of type [`Uint128`] which is the multiple each member payout is rounded down to.
+pub fn try_distribute_group_with_reserve(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    group_addr: String,
+    reserve: Uint128,
+    reserve_to: String,
+    precision: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let amount = sent_native_amount(&info, &denom)?;
+    if amount < reserve {
+        return Err(ContractError::MismatchedAssetAmount {});
+    }
+
+    let group_addr = deps.api.addr_validate(&group_addr)?;
+    let weights: Vec<(String, Uint128)> = query_group_members(deps.as_ref(), &group_addr)?
+        .into_iter()
+        .filter(|(_, weight)| !weight.is_zero())
+        .collect();
+
+    let mut recipients = split_by_weight(amount - reserve, weights, precision)?;
+    if !reserve.is_zero() {
+        match recipients
+            .iter_mut()
+            .find(|recipient| recipient.recipient == reserve_to)
+        {
+            Some(member) => member.amount += reserve,
+            None => recipients.insert(
+                0,
+                Recipient {
+                    recipient: reserve_to,
+                    amount: reserve,
+                    deferred: false,
+                },
+            ),
+        }
+    }
+
+    try_distribute_native(
+        deps,
+        info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins where recipients are only paid if their
+/// condition is true. The sent amount must cover every recipient; the amounts of recipients
+/// whose condition is false are refunded to the sender, so if every condition is false the whole
+/// amount is refunded. Every recipient needs exactly one condition.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount to distribute to.
+///
+/// - **conditions** is an object of type [`Vec<(String, bool)>`] which is the condition flag for every recipient address.
+pub fn try_distribute_conditional(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<Recipient>,
+    conditions: Vec<(String, bool)>,
+) -> Result<Response, ContractError> {
+    let event_verbosity = CONFIG.load(deps.storage)?.event_verbosity;
+    let amount = sent_native_amount(&info, &denom)?;
+    let sum_recipient_amount: Uint128 =
+        recipients.iter().fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
+    if amount != sum_recipient_amount {
+        return Err(ContractError::MismatchedAssetAmount {});
+    }
+
+    let mut condition_map: BTreeMap<String, bool> = BTreeMap::new();
+    for (recipient, condition) in conditions {
+        if condition_map.contains_key(&recipient) {
+            return Err(ContractError::DuplicateCondition { recipient });
+        }
+        condition_map.insert(recipient, condition);
+    }
+    let mut paid: Vec<Recipient> = vec![];
+    let mut refund = Uint128::zero();
+    for recipient in recipients {
+        match condition_map.get(&recipient.recipient) {
+            Some(true) => paid.push(recipient),
+            Some(false) => refund += recipient.amount,
+            None => {
+                return Err(ContractError::MissingCondition {
+                    recipient: recipient.recipient,
+                })
+            }
+        }
+    }
+
+    // distribute as if only the paid amount had been sent, then refund the rest
+    let mut response = if paid.is_empty() {
+        Response::new().add_attribute("action", "distribute_native")
+    } else {
+        let paid_info = MessageInfo {
+            sender: info.sender.clone(),
+            funds: coins((amount - refund).u128(), denom.clone()),
+        };
+        try_distribute_native(
+            deps,
+            paid_info,
+            denom.clone(),
+            paid,
+            FailurePolicy::AbortAll,
+            false,
+            None,
+        )?
+    };
+    if !refund.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(refund.u128(), denom),
+        });
+    }
+    Ok(add_details(
+        &event_verbosity,
+        response,
+        vec![attr("refund", refund)],
+    ))
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins to recipients identified by chain-agnostic
+/// identifiers, resolved to local addresses through the configured bridge registry. Under
+/// [`FailurePolicy::SkipInvalid`] unresolvable identifiers are skipped and reported in the
+/// `unresolved` attribute; otherwise the whole distribution is rejected. The resolved addresses
+/// are screened like those of a native distribution and the amounts of skipped recipients are
+/// refunded to the sender, the whole amount if no recipient is left.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<IdentifiedRecipient>`] which is the list of recipient identifier and amount.
+///
+/// - **failure_policy** is an object of type [`FailurePolicy`] which decides how unresolvable identifiers and invalid resolved addresses are handled.
+pub fn try_distribute_by_identifier(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<IdentifiedRecipient>,
+    failure_policy: FailurePolicy,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    let config = CONFIG.load(deps.storage)?;
+    let registry = config
+        .bridge_registry
+        .ok_or_else(|| ContractError::Generic("bridge registry is not configured".to_string()))?;
+
+    let amount = sent_native_amount(&info, &denom)?;
+    let sum_recipient_amount: Uint128 =
+        recipients.iter().fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
+    if amount != sum_recipient_amount {
+        return Err(ContractError::MismatchedAssetAmount {});
+    }
+
+    let mut resolved: Vec<Recipient> = vec![];
+    let mut unresolved: Vec<String> = vec![];
+    for recipient in recipients {
+        let resolution: ResolveResponse = deps.querier.query_wasm_smart(
+            &registry,
+            &RegistryQueryMsg::Resolve {
+                id: recipient.id.clone(),
+            },
+        )?;
+        match (resolution.address, &failure_policy) {
+            (Some(address), _) => resolved.push(Recipient {
+                recipient: address,
+                amount: recipient.amount,
+                deferred: false,
+            }),
+            (None, FailurePolicy::AbortAll) => {
+                return Err(ContractError::Generic(format!(
+                    "unresolvable recipient identifier: {}",
+                    recipient.id
+                )))
+            }
+            (None, FailurePolicy::SkipInvalid) => unresolved.push(recipient.id),
+        }
+    }
+
+    // screen the resolved addresses here so that what is skipped can be refunded
+    let FilteredRecipients {
+        kept: resolved,
+        skipped,
+        redirected,
+    } = filter_recipients(deps.as_ref(), resolved, &failure_policy)?;
+    let resolved_amount: Uint128 = resolved
+        .iter()
+        .fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
+    let refund = amount - resolved_amount;
+
+    // distribute as if only the resolved amount had been sent, then refund the rest
+    let mut response = if resolved.is_empty() {
+        Response::new().add_attribute("action", "distribute_native")
+    } else {
+        let resolved_info = MessageInfo {
+            sender: info.sender.clone(),
+            funds: coins(resolved_amount.u128(), denom.clone()),
+        };
+        try_distribute_native(
+            deps,
+            resolved_info,
+            denom.clone(),
+            resolved,
+            FailurePolicy::AbortAll,
+            false,
+            None,
+        )?
+    };
+    if !refund.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(refund.u128(), denom),
+        });
+    }
+    let mut details = vec![];
+    if !unresolved.is_empty() {
+        details.push(attr("unresolved", unresolved.join(",")));
+    }
+    if !skipped.is_empty() {
+        details.push(attr("skipped", skipped.join(",")));
+    }
+    if !redirected.is_empty() {
+        details.push(attr("redirected", redirected.join(",")));
+    }
+    Ok(add_details(&config.event_verbosity, response, details))
+}
+
+/// ## Description
+/// Returns all members of a cw4 group and their weight, following the group's pagination.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **group_addr** is a reference to an object of type [`Addr`] which is the address of the cw4 group contract.
+fn query_group_members(deps: Deps, group_addr: &Addr) -> StdResult<Vec<(String, Uint128)>> {
+    let mut members: Vec<(String, Uint128)> = vec![];
+    let mut start_after: Option<String> = None;
+    loop {
+        let page: Cw4MemberListResponse = deps.querier.query_wasm_smart(
+            group_addr,
+            &Cw4QueryMsg::ListMembers {
+                start_after: start_after.clone(),
+                limit: None,
+            },
+        )?;
+        match page.members.last() {
+            Some(last) => start_after = Some(last.addr.clone()),
+            None => break,
+        }
+        members.extend(
+            page.members
+                .into_iter()
+                .map(|member| (member.addr, Uint128::from(member.weight))),
+        );
+    }
+    Ok(members)
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins as a CW20 token. The sent coins are wrapped
+/// by the wrapper contract, which is also the wrapped token, and the wrapped tokens are
+/// distributed once the wrapper replies, see [`reply`].
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to wrap.
+///
+/// - **wrapper** is an object of type [`String`] which is the address of the wrapper contract.
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount to distribute to.
+pub fn try_distribute_wrapped(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    wrapper: String,
+    recipients: Vec<Recipient>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    validate_recipient_amounts(&recipients)?;
+    if recipients.iter().any(|recipient| recipient.deferred) {
+        return Err(ContractError::UnsupportedDeferred {});
+    }
+    let wrapper = deps.api.addr_validate(&wrapper)?;
+    let recipients = filter_recipients(deps.as_ref(), recipients, &FailurePolicy::AbortAll)?.kept;
+
+    // validate sent coin amount matches sum(recipient amounts)
+    let amount = sent_native_amount(&info, &denom)?;
+    let sum_recipient_amount: Uint128 =
+        recipients.iter().fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
+    if amount != sum_recipient_amount {
+        return Err(ContractError::MismatchedAssetAmount {});
+    }
+
+    // check for duplicate recipient address
+    if has_duplicate_address(
+        recipients
+            .iter()
+            .map(|recipient| recipient.recipient.as_str()),
+    ) {
+        return Err(ContractError::DuplicateRecipient {});
+    }
+
+    // bound the number of transfers built in one call
+    assert_max_recipients(deps.as_ref(), recipients.len())?;
+
+    // wrapped tokens cannot be deferred
+    assert_opted_in(deps.as_ref(), &recipients)?;
+    assert_approved(deps.as_ref(), wrapper.as_str(), &recipients)?;
+    for recipient in recipients.iter() {
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+        assert_lifetime_cap(
+            deps.as_ref(),
+            &recipient_addr,
+            wrapper.as_str(),
+            recipient.amount,
+        )?;
+    }
+
+    // the minted amount is the growth of the contract's wrapped token balance
+    let balance: BalanceResponse = deps.querier.query_wasm_smart(
+        &wrapper,
+        &Cw20QueryMsg::Balance {
+            address: env.contract.address.to_string(),
+        },
+    )?;
+    PENDING_WRAP.save(
+        deps.storage,
+        &PendingWrap {
+            wrapper: wrapper.clone(),
+            balance_before: balance.balance,
+            recipients,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: wrapper.to_string(),
+                msg: to_binary(&WrapperExecuteMsg::Wrap {})?,
+                funds: info.funds,
+            },
+            WRAP_REPLY_ID,
+        ))
+        .add_attribute("action", "distribute_wrapped"))
+}
+
+/// ## Description
+/// Returns to the sender the amounts of their best-effort native and non-atomic multi-asset
+/// distributions whose transfers failed, see [`reply`].
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the failed transfers.
+pub fn try_reclaim_failed(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    let amount = RECLAIMABLE
+        .may_load(deps.storage, (&info.sender, &denom))?
+        .unwrap_or_default();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToReclaim { denom });
+    }
+    RECLAIMABLE.remove(deps.storage, (&info.sender, &denom));
+    RESERVED.update(deps.storage, &denom, |reserved| -> StdResult<_> {
+        Ok(reserved.unwrap_or_default().checked_sub(amount)?)
+    })?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(amount.u128(), denom),
+        })
+        .add_attribute("action", "reclaim_failed")
+        .add_attribute("amount", amount))
+}
+
+/// ## Description
+/// Splits the sent native Cosmos SDK coins equally between the borrowers (see
+/// [`split_by_weight`] for the dust handling) and repays each borrower's debt in the lending
+/// contract with `min(share, debt)`. The part of the shares exceeding the debts is refunded to
+/// the sender.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **lending_addr** is an object of type [`String`] which is the address of the lending contract.
+///
+/// - **borrowers** is an object of type [`Vec<String>`] which is the list of borrower addresses.
+pub fn try_repay_debts(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    lending_addr: String,
+    borrowers: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    let amount = sent_native_amount(&info, &denom)?;
+    let lending_addr = deps.api.addr_validate(&lending_addr)?;
+    if has_duplicate_address(borrowers.iter().map(|borrower| borrower.as_str())) {
+        return Err(ContractError::DuplicateRecipient {});
+    }
+    assert_max_recipients(deps.as_ref(), borrowers.len())?;
+
+    let shares = split_by_weight(
+        amount,
+        borrowers
+            .into_iter()
+            .map(|borrower| (borrower, Uint128::new(1)))
+            .collect(),
+        None,
+    )?;
+
+    let mut repay_msgs: Vec<CosmosMsg> = vec![];
+    let mut refund = Uint128::zero();
+    for share in shares {
+        deps.api.addr_validate(&share.recipient)?;
+        let debt: DebtResponse = deps.querier.query_wasm_smart(
+            &lending_addr,
+            &LendingQueryMsg::Debt {
+                borrower: share.recipient.clone(),
+            },
+        )?;
+        let repay = share.amount.min(debt.amount);
+        refund += share.amount - repay;
+        if repay.is_zero() {
+            continue;
+        }
+        repay_msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: lending_addr.to_string(),
+            funds: coins(repay.u128(), denom.clone()),
+            msg: to_binary(&LendingExecuteMsg::RepayOnBehalf {
+                borrower: share.recipient,
+            })?,
+        }));
+    }
+
+    let mut response = add_details(
+        &CONFIG.load(deps.storage)?.event_verbosity,
+        Response::new()
+            .add_messages(repay_msgs)
+            .add_attribute("action", "repay_debts"),
+        vec![attr("refund", refund)],
+    );
+    if !refund.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(refund.u128(), denom),
+        });
+    }
+    Ok(response)
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins where each recipient receives their base
+/// amount plus a sponsor match of `base * match_bps / 10000`, truncated. The sent amount must
+/// cover both the base amounts and the match.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and base amount.
+///
+/// - **sponsor** is an object of type [`String`] which is the address of the sponsor funding the match.
+///
+/// - **match_bps** is an object of type [`u16`] which is the match in basis points of the base amount.
+pub fn try_distribute_matched(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<Recipient>,
+    sponsor: String,
+    match_bps: u16,
+) -> Result<Response, ContractError> {
+    let event_verbosity = CONFIG.load(deps.storage)?.event_verbosity;
+    let sponsor = deps.api.addr_validate(&sponsor)?;
+
+    let mut matched = Uint128::zero();
+    let recipients: Vec<Recipient> = recipients
+        .into_iter()
+        .map(|mut recipient| {
+            let match_amount = recipient.amount.multiply_ratio(match_bps, 10_000u128);
+            matched += match_amount;
+            recipient.amount += match_amount;
+            recipient
+        })
+        .collect();
+
+    let response = try_distribute_native(
+        deps,
+        info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )?;
+    Ok(add_details(
+        &event_verbosity,
+        response,
+        vec![attr("sponsor", sponsor), attr("matched_amount", matched)],
+    ))
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins where every recipient receives the same USD
+/// value, priced by the oracle. With a single funded denom equal value means equal amounts, so
+/// the sent amount is split evenly and the remainder (dust) goes to the last recipient. The price
+/// used is recorded in the `price` attribute.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<String>`] which is the list of recipient addresses.
+///
+/// - **oracle_addr** is an object of type [`String`] which is the address of the price oracle contract.
+pub fn try_distribute_equal_value(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<String>,
+    oracle_addr: String,
+) -> Result<Response, ContractError> {
+    let event_verbosity = CONFIG.load(deps.storage)?.event_verbosity;
+    let oracle_addr = deps.api.addr_validate(&oracle_addr)?;
+    let price: PriceResponse = deps.querier.query_wasm_smart(
+        &oracle_addr,
+        &OracleQueryMsg::Price {
+            denom: denom.clone(),
+        },
+    )?;
+    if price.price.is_zero() {
+        return Err(ContractError::Generic(
+            "oracle returned a zero price".to_string(),
+        ));
+    }
+
+    let amount = sent_native_amount(&info, &denom)?;
+    let recipients = split_by_weight(
+        amount,
+        recipients
+            .into_iter()
+            .map(|recipient| (recipient, Uint128::new(1)))
+            .collect(),
+        None,
+    )?;
+    let value = price.price * recipients[0].amount;
+
+    let response = try_distribute_native(
+        deps,
+        info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )?;
+    Ok(add_details(
+        &event_verbosity,
+        response,
+        vec![
+            attr("oracle", oracle_addr),
+            attr("price", price.price.to_string()),
+            attr("value_per_recipient", value),
+        ],
+    ))
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins split by fixed shares, so a recurring split
+/// (e.g. 50/30/20) works for any sent amount. Each payout is `amount * weight / total_weight`,
+/// truncated; the truncated remainder (dust) is sent to the last recipient so nothing is left in
+/// the contract.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **shares** is an object of type [`Vec<Share>`] which is the list of recipient address and weight.
+pub fn try_distribute_native_by_shares(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    shares: Vec<Share>,
+) -> Result<Response, ContractError> {
+    let amount = sent_native_amount(&info, &denom)?;
+    let recipients = split_by_weight(amount, share_weights(shares), None)?;
+
+    try_distribute_native(
+        deps,
+        info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins split equally between the recipients, see
+/// [`split_equally`].
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<String>`] which is the list of recipient addresses.
+pub fn try_distribute_native_equal(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<String>,
+) -> Result<Response, ContractError> {
+    let amount = sent_native_amount(&info, &denom)?;
+    let recipients = split_equally(amount, recipients)?;
+
+    try_distribute_native(
+        deps,
+        info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins to recipients whose amounts are expressed in a
+/// logical currency. Each amount is multiplied by the configured rate of the currency and rounded
+/// down, and the sent coins must match the converted total in the currency's denom. A converted
+/// amount that overflows is rejected.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **currency** is an object of type [`String`] which is the logical currency of the amounts.
+///
+/// - **recipients** is an object of type [`Vec<(String, Uint128)>`] which is the list of recipient address and amount in currency units.
+pub fn try_distribute_in_currency(
+    deps: DepsMut,
+    info: MessageInfo,
+    currency: String,
+    recipients: Vec<(String, Uint128)>,
+) -> Result<Response, ContractError> {
+    let rate = CONFIG
+        .load(deps.storage)?
+        .currencies
+        .into_iter()
+        .find(|rate| rate.currency == currency)
+        .ok_or(ContractError::UnknownCurrency { currency })?;
+    let recipients: Vec<Recipient> = recipients
+        .into_iter()
+        .map(|(recipient, units)| -> StdResult<_> {
+            let amount = units
+                .checked_multiply_ratio(rate.rate.atomics(), Decimal::one().atomics())
+                .map_err(|err| StdError::generic_err(err.to_string()))?;
+            Ok(Recipient {
+                recipient,
+                amount,
+                deferred: false,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    try_distribute_native(
+        deps,
+        info,
+        rate.denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins where `amount * compound_bps / 10000`,
+/// truncated, of every recipient's amount is staked in the staking contract on their behalf and
+/// the rest is sent to them directly. The recipient's total counts the full amount. A stake
+/// cannot be deferred, so deferred recipients are rejected and, if opt-in is required, every
+/// recipient must have opted in.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount.
+///
+/// - **compound_bps** is an object of type [`u16`] which is the staked share in basis points.
+///
+/// - **staking_contract** is an object of type [`String`] which is the address of the staking contract.
+pub fn try_distribute_with_compound(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<Recipient>,
+    compound_bps: u16,
+    staking_contract: String,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    if compound_bps > 10_000 {
+        return Err(ContractError::Generic(
+            "compound_bps must not exceed 10000".to_string(),
+        ));
+    }
+    let staking_contract = deps.api.addr_validate(&staking_contract)?;
+    validate_recipient_amounts(&recipients)?;
+    if recipients.iter().any(|recipient| recipient.deferred) {
+        return Err(ContractError::UnsupportedDeferred {});
+    }
+    let FilteredRecipients {
+        kept: recipients,
+        redirected,
+        ..
+    } = filter_recipients(deps.as_ref(), recipients, &FailurePolicy::AbortAll)?;
+
+    // validate sent coin amount matches sum(recipient amounts)
+    let amount = sent_native_amount(&info, &denom)?;
+    let sum_recipient_amount: Uint128 =
+        recipients.iter().fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
+    if amount != sum_recipient_amount {
+        return Err(ContractError::MismatchedAssetAmount {});
+    }
+
+    // reject dust-sized distributions
+    check_min_total(deps.as_ref(), sum_recipient_amount)?;
+
+    // check for duplicate recipient address
+    if has_duplicate_address(
+        recipients
+            .iter()
+            .map(|recipient| recipient.recipient.as_str()),
+    ) {
+        return Err(ContractError::DuplicateRecipient {});
+    }
+
+    // bound the number of transfers built in one call
+    assert_max_recipients(deps.as_ref(), recipients.len())?;
+
+    // stakes cannot be deferred
+    assert_opted_in(deps.as_ref(), &recipients)?;
+    assert_approved(deps.as_ref(), &denom, &recipients)?;
+
+    let mut transfer_msgs: Vec<CosmosMsg> = vec![];
+    let mut events: Vec<Event> = vec![];
+    let mut compounded = Uint128::zero();
+    for recipient in recipients.iter() {
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+        assert_lifetime_cap(deps.as_ref(), &recipient_addr, &denom, recipient.amount)?;
+        add_recipient_total(deps.storage, &recipient_addr, &denom, recipient.amount)?;
+        events.push(recipient_event("distribute_transfer", recipient));
+
+        let compound = recipient.amount.multiply_ratio(compound_bps, 10_000u128);
+        let direct = recipient.amount - compound;
+        compounded += compound;
+        if !direct.is_zero() {
+            transfer_msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.recipient.clone(),
+                amount: coins(direct.u128(), denom.clone()),
+            }));
+        }
+        if !compound.is_zero() {
+            transfer_msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: staking_contract.to_string(),
+                funds: coins(compound.u128(), denom.clone()),
+                msg: to_binary(&StakingExecuteMsg::StakeFor {
+                    recipient: recipient.recipient.clone(),
+                })?,
+            }));
+        }
+    }
+
+    let event_verbosity = CONFIG.load(deps.storage)?.event_verbosity;
+    let mut response = Response::new()
+        .add_messages(transfer_msgs)
+        .add_attribute("action", "distribute_with_compound");
+    if event_verbosity != EventLevel::Minimal {
+        response = response
+            .add_attribute("denom", &denom)
+            .add_attribute("total_amount", sum_recipient_amount)
+            .add_attribute("compounded_amount", compounded)
+            .add_attribute("num_recipients", recipients.len().to_string());
+        if !redirected.is_empty() {
+            response = response.add_attribute("redirected", redirected.join(","));
+        }
+    }
+    if event_verbosity == EventLevel::Full {
+        response = response.add_events(events);
+    }
+    if let Some(hook_msg) = post_hook_msg(
+        deps.as_ref(),
+        &info,
+        coins(sum_recipient_amount.u128(), denom),
+        recipients.len() as u32,
+    )? {
+        response = response.add_message(hook_msg);
+    }
+    Ok(response)
+}
+
+/// ## Description
+/// Handles distribution of the sent native Cosmos SDK coins as a quadratic funding matching pool.
+/// Each project is weighted by the square of the sum of the square roots of its contributions,
+/// and the pool is split by weight with the same dust handling as the other weighted
+/// distributions. Contributions are scaled by 10^6 before taking integer square roots, so small
+/// contributions are not rounded away.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **contributions** is an object of type [`Vec<(String, Vec<Uint128>)>`] which is the list of project addresses and their contributions.
+pub fn try_distribute_quadratic(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    contributions: Vec<(String, Vec<Uint128>)>,
+) -> Result<Response, ContractError> {
+    let mut weights: Vec<(String, Uint128)> = vec![];
+    for (project, amounts) in contributions {
+        let mut sum_sqrt = Uint128::zero();
+        for amount in amounts {
+            let scaled = amount
+                .checked_mul(Uint128::new(1_000_000))
+                .map_err(StdError::from)?;
+            sum_sqrt = sum_sqrt
+                .checked_add(integer_sqrt(scaled))
+                .map_err(StdError::from)?;
+        }
+        let weight = sum_sqrt.checked_mul(sum_sqrt).map_err(StdError::from)?;
+        weights.push((project, weight));
+    }
+
+    let amount = sent_native_amount(&info, &denom)?;
+    let recipients = split_by_weight(amount, weights, None)?;
+
+    try_distribute_native(
+        deps,
+        info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )
+}
+
+/// ## Description
+/// Returns the square root of `n` rounded down, computed with Newton's method.
+///
+/// ## Params
+/// - **n** is an object of type [`Uint128`] which is the number to take the square root of.
+fn integer_sqrt(n: Uint128) -> Uint128 {
+    let n = n.u128();
+    if n < 2 {
+        return Uint128::new(n);
+    }
+    // start at n / 2 + 1, which is at least the root, so `x + n / x` cannot overflow
+    let mut x = n / 2 + 1;
+    let mut y = (x + n / x) / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    Uint128::new(x)
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins split equally between `winners` candidates,
+/// with the dust going to the last winner drawn.
+///
+/// Winners are drawn deterministically: draw `i` hashes `seed`, the block height, the block
+/// time, the chain id and `i` with SHA-256 and picks the remaining candidate at the first 8 bytes
+/// of the hash modulo the number of remaining candidates. The same seed, candidate order and
+/// block always draw the same winners, so anyone can reproduce the draw from the transaction.
+/// The caller chooses the seed, so it should come from a source the caller cannot bias, such as
+/// a public randomness beacon committed to before the candidates are known.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **candidates** is an object of type [`Vec<String>`] which is the list of candidate addresses.
+///
+/// - **winners** is an object of type [`u32`] which is the number of winners to draw.
+///
+/// - **seed** is an object of type [`Binary`] which is the seed mixed with the block data.
+pub fn try_distribute_random(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    mut candidates: Vec<String>,
+    winners: u32,
+    seed: Binary,
+) -> Result<Response, ContractError> {
+    let event_verbosity = CONFIG.load(deps.storage)?.event_verbosity;
+    if has_duplicate_address(candidates.iter().map(|candidate| candidate.as_str())) {
+        return Err(ContractError::DuplicateRecipient {});
+    }
+    if winners == 0 || winners as usize > candidates.len() {
+        return Err(ContractError::Generic(format!(
+            "winners must be between 1 and {}",
+            candidates.len()
+        )));
+    }
+
+    let mut drawn: Vec<String> = vec![];
+    for i in 0..winners {
+        let hash = Sha256::new()
+            .chain(seed.as_slice())
+            .chain(env.block.height.to_be_bytes())
+            .chain(env.block.time.nanos().to_be_bytes())
+            .chain(env.block.chain_id.as_bytes())
+            .chain(i.to_be_bytes())
+            .finalize();
+        let mut index = [0u8; 8];
+        index.copy_from_slice(&hash[..8]);
+        let index = u64::from_be_bytes(index) % candidates.len() as u64;
+        drawn.push(candidates.remove(index as usize));
+    }
+
+    let amount = sent_native_amount(&info, &denom)?;
+    let recipients = split_by_weight(
+        amount,
+        drawn
+            .iter()
+            .map(|winner| (winner.clone(), Uint128::new(1)))
+            .collect(),
+        None,
+    )?;
+
+    let response = try_distribute_native(
+        deps,
+        info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )?;
+    Ok(add_details(
+        &event_verbosity,
+        response,
+        vec![attr("winners", drawn.join(","))],
+    ))
+}
+
+/// ## Description
+/// Transfers ownership of the contract to `new_owner`. Only the current owner may call this.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **new_owner** is an object of type [`String`] which is the address of the new owner.
+pub fn try_update_owner(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+
+    CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+        config.owner = Some(new_owner.clone());
+        Ok(config)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_owner")
+        .add_attribute("owner", new_owner))
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins into per-recipient escrows instead of sending
+/// them directly. Each recipient may claim their escrow once `dispute_window` seconds have
+/// passed, and until then the owner may claw it back, so a clawback never depends on the
+/// recipient still holding the funds. Escrowed amounts are reserved like deferred balances.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount to escrow.
+///
+/// - **dispute_window** is an object of type [`u64`] which is the length of the dispute window in seconds.
+pub fn try_distribute_with_clawback(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<Recipient>,
+    dispute_window: u64,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    validate_recipient_amounts(&recipients)?;
+    if recipients.iter().any(|recipient| recipient.deferred) {
+        return Err(ContractError::UnsupportedDeferred {});
+    }
+    let recipients = filter_recipients(deps.as_ref(), recipients, &FailurePolicy::AbortAll)?.kept;
+
+    let amount = sent_native_amount(&info, &denom)?;
+    let sum_recipient_amount: Uint128 =
+        recipients.iter().fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
+    if amount != sum_recipient_amount {
+        return Err(ContractError::MismatchedAssetAmount {});
+    }
+    if has_duplicate_address(
+        recipients
+            .iter()
+            .map(|recipient| recipient.recipient.as_str()),
+    ) {
+        return Err(ContractError::DuplicateRecipient {});
+    }
+    assert_max_recipients(deps.as_ref(), recipients.len())?;
+    check_min_total(deps.as_ref(), sum_recipient_amount)?;
+    assert_approved(deps.as_ref(), &denom, &recipients)?;
+
+    let release_at = dispute_window
+        .checked_mul(1_000_000_000)
+        .and_then(|window| env.block.time.nanos().checked_add(window))
+        .map(Timestamp::from_nanos)
+        .ok_or_else(|| ContractError::Generic("dispute_window is too long".to_string()))?;
+    let mut id = ESCROW_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    let mut ids: Vec<String> = vec![];
+    for recipient in recipients {
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+        assert_lifetime_cap(deps.as_ref(), &recipient_addr, &denom, recipient.amount)?;
+        ESCROWED.update(
+            deps.storage,
+            (&recipient_addr, &denom),
+            |escrowed| -> StdResult<_> { Ok(escrowed.unwrap_or_default() + recipient.amount) },
+        )?;
+        let escrow = Escrow {
+            recipient: recipient_addr,
+            denom: denom.clone(),
+            amount: recipient.amount,
+            release_at,
+        };
+        ESCROWS.save(deps.storage, id, &escrow)?;
+        ids.push(id.to_string());
+        id += 1;
+    }
+    ESCROW_COUNT.save(deps.storage, &id)?;
+    RESERVED.update(deps.storage, &denom, |reserved| -> StdResult<_> {
+        Ok(reserved
+            .unwrap_or_default()
+            .checked_add(sum_recipient_amount)?)
+    })?;
+
+    Ok(add_details(
+        &CONFIG.load(deps.storage)?.event_verbosity,
+        Response::new().add_attribute("action", "distribute_with_clawback"),
+        vec![
+            attr("denom", denom),
+            attr("total_amount", sum_recipient_amount),
+            attr("release_at", release_at.seconds().to_string()),
+            attr("escrow_ids", ids.join(",")),
+        ],
+    ))
+}
+
+/// ## Description
+/// Handles distribution of `free_balance * share_bps / 10000` of the contract's own native
+/// balance by weight, leaving the rest in the contract. The free balance excludes amounts
+/// reserved for deferred and escrowed recipients and for senders of failed best-effort
+/// transfers. The share is split with the same dust handling as the other weighted
+/// distributions. No funds may be sent with the message, they would otherwise silently add to
+/// the balance left in the contract.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<WeightedRecipient>`] which is the list of recipient address and weight.
+///
+/// - **share_bps** is an object of type [`u16`] which is the share of the free balance in basis points.
+pub fn try_distribute_balance_share(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<WeightedRecipient>,
+    share_bps: u16,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedFunds {});
+    }
+    if share_bps > 10_000 {
+        return Err(ContractError::Generic(
+            "share_bps must not exceed 10000".to_string(),
+        ));
+    }
+
+    let event_verbosity = CONFIG.load(deps.storage)?.event_verbosity;
+    let balance = free_balance(deps.as_ref(), &env, &denom)?;
+    let amount = balance.multiply_ratio(share_bps, 10_000u128);
+    let (recipients, below_minimum) = split_with_minimums(amount, recipients, None)?;
+
+    // distribute as if the share had been sent with the message
+    let share_info = MessageInfo {
+        sender: info.sender,
+        funds: coins(amount.u128(), denom.clone()),
+    };
+    let response = try_distribute_native(
+        deps,
+        share_info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )?;
+    let mut details = vec![
+        attr("free_balance", balance),
+        attr("share_bps", share_bps.to_string()),
+    ];
+    if !below_minimum.is_empty() {
+        details.push(attr("below_minimum", below_minimum.join(",")));
+    }
+    Ok(add_details(&event_verbosity, response, details))
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins proportional to each address's balance
+/// increase between two snapshots. Both snapshots must list the same addresses in the same
+/// order; addresses whose balance did not increase receive nothing. The reward is split with the
+/// same dust handling as the other weighted distributions.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **reward_denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **start_balances** is an object of type [`Vec<(String, Uint128)>`] which is the list of addresses and balances at the start.
+///
+/// - **end_balances** is an object of type [`Vec<(String, Uint128)>`] which is the list of addresses and balances at the end.
+pub fn try_distribute_by_diff(
+    deps: DepsMut,
+    info: MessageInfo,
+    reward_denom: String,
+    start_balances: Vec<(String, Uint128)>,
+    end_balances: Vec<(String, Uint128)>,
+) -> Result<Response, ContractError> {
+    if start_balances.len() != end_balances.len()
+        || start_balances
+            .iter()
+            .zip(end_balances.iter())
+            .any(|((start, _), (end, _))| start != end)
+    {
+        return Err(ContractError::Generic(
+            "start and end balances must list the same addresses in the same order".to_string(),
+        ));
+    }
+
+    let diffs: Vec<(String, Uint128)> = start_balances
+        .into_iter()
+        .zip(end_balances)
+        .filter(|((_, start), (_, end))| end > start)
+        .map(|((address, start), (_, end))| (address, end - start))
+        .collect();
+
+    let amount = sent_native_amount(&info, &reward_denom)?;
+    let recipients = split_by_weight(amount, diffs, None)?;
+
+    try_distribute_native(
+        deps,
+        info,
+        reward_denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )
+}
+
+/// ## Description
+/// Swaps the contract's free balance of every denom in `assets` into `target_denom` through the
+/// swap contract, skipping denoms without a free balance. Only the last swap replies, with
+/// `SWAP_REPLY_ID`, so the proceeds of all swaps are sent to the owner at once, see
+/// [`reply_swap`].
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **assets** is an object of type [`Vec<String>`] which is the list of denoms to sweep.
+///
+/// - **target_denom** is an object of type [`String`] which is the denom the dust is swapped into.
+///
+/// - **swap_contract** is an object of type [`String`] which is the address of the swap contract.
+pub fn try_sweep_dust_to_asset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<String>,
+    target_denom: String,
+    swap_contract: String,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedFunds {});
+    }
+    if assets.contains(&target_denom) {
+        return Err(ContractError::Generic(
+            "target denom cannot be swept into itself".to_string(),
+        ));
+    }
+    if has_duplicate_address(assets.iter().map(|asset| asset.as_str())) {
+        return Err(ContractError::Generic("duplicate asset".to_string()));
+    }
+    let swap_contract = deps.api.addr_validate(&swap_contract)?;
+
+    let mut dust: Vec<Coin> = vec![];
+    for asset in assets.into_iter() {
+        let amount = free_balance(deps.as_ref(), &env, &asset)?;
+        if !amount.is_zero() {
+            dust.push(Coin {
+                denom: asset,
+                amount,
+            });
+        }
+    }
+    if dust.is_empty() {
+        return Err(ContractError::Generic("no dust to sweep".to_string()));
+    }
+
+    let balance_before = deps
+        .querier
+        .query_balance(&env.contract.address, &target_denom)?
+        .amount;
+    PENDING_SWAP.save(
+        deps.storage,
+        &PendingSwap {
+            recipient: info.sender,
+            target_denom: target_denom.clone(),
+            balance_before,
+        },
+    )?;
+
+    let last = dust.len() - 1;
+    let mut swap_msgs: Vec<SubMsg> = vec![];
+    for (i, coin) in dust.iter().enumerate() {
+        let msg = WasmMsg::Execute {
+            contract_addr: swap_contract.to_string(),
+            msg: to_binary(&SwapExecuteMsg::Swap {
+                ask_denom: target_denom.clone(),
+            })?,
+            funds: vec![coin.clone()],
+        };
+        swap_msgs.push(if i == last {
+            SubMsg::reply_on_success(msg, SWAP_REPLY_ID)
+        } else {
+            SubMsg::new(msg)
+        });
+    }
+
+    let swept: Vec<String> = dust.iter().map(|coin| coin.to_string()).collect();
+    Ok(add_details(
+        &CONFIG.load(deps.storage)?.event_verbosity,
+        Response::new()
+            .add_submessages(swap_msgs)
+            .add_attribute("action", "sweep_dust_to_asset"),
+        vec![
+            attr("target_denom", target_denom),
+            attr("swept", swept.join(",")),
+        ],
+    ))
+}
+
+/// ## Description
+/// Handles distribution of native Cosmos SDK coins from the contract's free balance. If the free
+/// balance does not cover the recipients, the shortfall is withdrawn from the configured vault
+/// contract with `VAULT_REPLY_ID` and the recipients are paid once it replies, see
+/// [`reply_vault`]. Without a vault contract the distribution fails. Only the owner may pay from
+/// the contract's balance.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount.
+pub fn try_distribute_from_balance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<Recipient>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    if !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedFunds {});
+    }
+    let required = check_batch_recipients(
+        deps.as_ref(),
+        &denom,
+        recipients.clone(),
+        &FailurePolicy::AbortAll,
+        true,
+    )?;
+    check_min_total(deps.as_ref(), required)?;
+
+    let available = free_balance(deps.as_ref(), &env, &denom)?;
+    if required <= available {
+        return distribute_from_balance(deps, info.sender, denom, recipients, required);
+    }
+    let vault =
+        CONFIG
+            .load(deps.storage)?
+            .vault_contract
+            .ok_or(ContractError::InsufficientFunds {
+                denom: denom.clone(),
+            })?;
+
+    let shortfall = required - available;
+    PENDING_VAULT.save(
+        deps.storage,
+        &PendingVault {
+            sender: info.sender,
+            denom: denom.clone(),
+            recipients,
+        },
+    )?;
+    let withdraw_msg = WasmMsg::Execute {
+        contract_addr: vault.to_string(),
+        msg: to_binary(&VaultExecuteMsg::Withdraw {
+            denom,
+            amount: shortfall,
+        })?,
+        funds: vec![],
+    };
+
+    Ok(add_details(
+        &CONFIG.load(deps.storage)?.event_verbosity,
+        Response::new()
+            .add_submessage(SubMsg::reply_on_success(withdraw_msg, VAULT_REPLY_ID))
+            .add_attribute("action", "distribute_from_balance"),
+        vec![attr("shortfall", shortfall)],
+    ))
+}
+
+/// ## Description
+/// Distributes `required` of the contract's free balance as if it had been sent with the
+/// message.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **sender** is an object of type [`Addr`] which is the address that started the distribution.
+///
+/// - **denom** is an object of type [`String`] which is the denomination of the native token to distribute.
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount.
+///
+/// - **required** is an object of type [`Uint128`] which is the sum of the recipient amounts.
+fn distribute_from_balance(
+    deps: DepsMut,
+    sender: Addr,
+    denom: String,
+    recipients: Vec<Recipient>,
+    required: Uint128,
+) -> Result<Response, ContractError> {
+    let balance_info = MessageInfo {
+        sender,
+        funds: coins(required.u128(), denom.clone()),
+    };
+    try_distribute_native(
+        deps,
+        balance_info,
+        denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )
+}
+
+/// ## Description
+/// Handles distribution of the sent native Cosmos SDK coins swapped into `denom`. The sent coin
+/// is swapped through the swap contract with `SWAP_DISTRIBUTION_REPLY_ID` and the proceeds are
+/// split by weight once it replies, see [`reply_swap_distribution`]. A swap returning less than
+/// `min_output` reverts the distribution.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denomination the sent coins are swapped into.
+///
+/// - **recipients** is an object of type [`Vec<WeightedRecipient>`] which is the list of recipient address and weight.
+///
+/// - **swap_contract** is an object of type [`String`] which is the address of the swap contract.
+///
+/// - **min_output** is an object of type [`Uint128`] which is the smallest swap output accepted.
+pub fn try_distribute_swapped(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    recipients: Vec<WeightedRecipient>,
+    swap_contract: String,
+    min_output: Uint128,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    let offer = match info.funds.as_slice() {
+        [offer] if offer.denom != denom && !offer.amount.is_zero() => offer.clone(),
+        _ => {
+            return Err(ContractError::Generic(format!(
+                "send exactly one coin of a denom other than {}",
+                denom
+            )))
+        }
+    };
+    if recipients.is_empty() {
+        return Err(ContractError::EmptyRecipients {});
+    }
+    let swap_contract = deps.api.addr_validate(&swap_contract)?;
+
+    let balance_before = deps
+        .querier
+        .query_balance(&env.contract.address, &denom)?
+        .amount;
+    PENDING_SWAP_DISTRIBUTION.save(
+        deps.storage,
+        &PendingSwapDistribution {
+            sender: info.sender,
+            denom: denom.clone(),
+            min_output,
+            balance_before,
+            recipients,
+        },
+    )?;
+    let swap_msg = WasmMsg::Execute {
+        contract_addr: swap_contract.to_string(),
+        msg: to_binary(&SwapExecuteMsg::Swap {
+            ask_denom: denom.clone(),
+        })?,
+        funds: vec![offer.clone()],
+    };
+
+    Ok(add_details(
+        &CONFIG.load(deps.storage)?.event_verbosity,
+        Response::new()
+            .add_submessage(SubMsg::reply_on_success(
+                swap_msg,
+                SWAP_DISTRIBUTION_REPLY_ID,
+            ))
+            .add_attribute("action", "distribute_swapped"),
+        vec![
+            attr("offer", offer.to_string()),
+            attr("denom", denom),
+            attr("min_output", min_output),
+        ],
+    ))
+}
+
+/// ## Description
+/// Cleans a recipient list the way off-chain tooling should before distributing: recipients
+/// with an invalid address, including one not matching the configured address format, are
+/// dropped, duplicate addresses are merged by summing their amounts in order of first appearance
+/// and zero amounts are dropped. No funds are moved; the cleaned
+/// list is emitted in the `recipients` attribute as comma-separated `address:amount` pairs and
+/// the dropped addresses, if any, in the `invalid` attribute.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipients to clean.
+pub fn try_clean_recipients(
+    deps: DepsMut,
+    recipients: Vec<Recipient>,
+) -> Result<Response, ContractError> {
+    let address_format = CONFIG.load(deps.storage)?.address_format;
+    let mut cleaned: Vec<Recipient> = vec![];
+    let mut invalid: Vec<String> = vec![];
+    for recipient in recipients {
+        if !matches_address_format(&recipient.recipient, &address_format)
+            || deps.api.addr_validate(&recipient.recipient).is_err()
+        {
+            invalid.push(recipient.recipient);
+            continue;
+        }
+        match cleaned
+            .iter_mut()
+            .find(|cleaned| cleaned.recipient == recipient.recipient)
+        {
+            Some(existing) => {
+                existing.amount = existing
+                    .amount
+                    .checked_add(recipient.amount)
+                    .map_err(StdError::overflow)?
+            }
+            None => cleaned.push(recipient),
+        }
+    }
+    cleaned.retain(|recipient| !recipient.amount.is_zero());
+
+    let pairs: Vec<String> = cleaned
+        .iter()
+        .map(|recipient| format!("{}:{}", recipient.recipient, recipient.amount))
+        .collect();
+    let mut response = Response::new()
+        .add_attribute("action", "clean_recipients")
+        .add_attribute("recipients", pairs.join(","))
+        .add_attribute("num_recipients", cleaned.len().to_string());
+    if !invalid.is_empty() {
+        response = response.add_attribute("invalid", invalid.join(","));
+    }
+    Ok(response)
+}
+
+/// ## Description
+/// Sends an escrow to its recipient once its dispute window has passed. The recipient must not be
+/// frozen and, if opt-in is required, must have opted in.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **id** is an object of type [`u64`] which is the id of the escrow.
+pub fn try_claim_after_window(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let escrow = ESCROWS.load(deps.storage, id)?;
+    if escrow.recipient != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if CONFIG.load(deps.storage)?.require_opt_in && !has_opted_in(deps.storage, &info.sender) {
+        return Err(ContractError::NotOptedIn {
+            address: info.sender.to_string(),
+        });
+    }
+    if is_frozen(deps.storage, &info.sender) {
+        return Err(ContractError::RecipientFrozen {
+            address: info.sender.to_string(),
+        });
+    }
+    if env.block.time < escrow.release_at {
+        return Err(ContractError::DisputeWindowOpen {});
+    }
+
+    release_escrow(deps.storage, id, &escrow)?;
+    add_recipient_total(
+        deps.storage,
+        &escrow.recipient,
+        &escrow.denom,
+        escrow.amount,
+    )?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: escrow.recipient.to_string(),
+            amount: coins(escrow.amount.u128(), escrow.denom),
+        })
+        .add_attribute("action", "claim_after_window")
+        .add_attribute("id", id.to_string())
+        .add_attribute("recipient", escrow.recipient))
+}
+
+/// ## Description
+/// Returns an escrow to the owner while its dispute window is still open.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **id** is an object of type [`u64`] which is the id of the escrow.
+pub fn try_clawback(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    let escrow = ESCROWS.load(deps.storage, id)?;
+    if env.block.time >= escrow.release_at {
+        return Err(ContractError::DisputeWindowClosed {});
+    }
+
+    release_escrow(deps.storage, id, &escrow)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(escrow.amount.u128(), escrow.denom),
+        })
+        .add_attribute("action", "clawback")
+        .add_attribute("id", id.to_string())
+        .add_attribute("recipient", escrow.recipient))
+}
+
+/// ## Description
+/// Removes an escrow and releases its amount from the reserved total.
+///
+/// ## Params
+/// - **storage** is a mutable reference to an object of type [`Storage`].
+///
+/// - **id** is an object of type [`u64`] which is the id of the escrow.
+///
+/// - **escrow** is a reference to an object of type [`Escrow`] which is the escrow to remove.
+fn release_escrow(storage: &mut dyn Storage, id: u64, escrow: &Escrow) -> StdResult<()> {
+    ESCROWS.remove(storage, id);
+    // escrows opened before the escrowed totals were tracked are not counted in them
+    ESCROWED.update(
+        storage,
+        (&escrow.recipient, &escrow.denom),
+        |escrowed| -> StdResult<_> {
+            Ok(escrowed.unwrap_or_default().saturating_sub(escrow.amount))
+        },
+    )?;
+    RESERVED.update(storage, &escrow.denom, |reserved| -> StdResult<_> {
+        Ok(reserved.unwrap_or_default().checked_sub(escrow.amount)?)
+    })?;
+    Ok(())
+}
+
+/// ## Description
+/// Freezes or unfreezes an address. Distributions to a frozen address are rejected, or the
+/// address is skipped if the deny policy drops denied recipients. Only the owner may call this.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **address** is an object of type [`String`] which is the address to freeze or unfreeze.
+///
+/// - **frozen** is an object of type [`bool`] which is whether the address is frozen.
+pub fn try_set_frozen(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    frozen: bool,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    let address = deps.api.addr_validate(&address)?;
+    if frozen {
+        FROZEN.save(deps.storage, &address, &true)?;
+    } else {
+        FROZEN.remove(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute(
+            "action",
+            if frozen {
+                "freeze_recipient"
+            } else {
+                "unfreeze_recipient"
+            },
+        )
+        .add_attribute("address", address))
+}
+
+/// ## Description
+/// Records that the sender consents to receiving distributions.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+pub fn try_opt_in(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    OPTED_IN.save(deps.storage, &info.sender, &true)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "opt_in")
+        .add_attribute("address", info.sender))
+}
+
+/// ## Description
+/// Sends all native balances deferred to the sender and clears them. A frozen sender cannot
+/// claim until unfrozen.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+pub fn try_claim_deferred(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if CONFIG.load(deps.storage)?.require_opt_in && !has_opted_in(deps.storage, &info.sender) {
+        return Err(ContractError::NotOptedIn {
+            address: info.sender.to_string(),
+        });
+    }
+    if is_frozen(deps.storage, &info.sender) {
+        return Err(ContractError::RecipientFrozen {
+            address: info.sender.to_string(),
+        });
+    }
+    let claimable: Vec<(String, Uint128)> = CLAIMABLE
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    if claimable.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    let mut amount: Vec<Coin> = vec![];
+    for (denom, claim_amount) in claimable {
+        CLAIMABLE.remove(deps.storage, (&info.sender, &denom));
+        RESERVED.update(deps.storage, &denom, |reserved| -> StdResult<_> {
+            Ok(reserved.unwrap_or_default().checked_sub(claim_amount)?)
+        })?;
+        add_recipient_total(deps.storage, &info.sender, &denom, claim_amount)?;
+        amount.push(Coin {
+            denom,
+            amount: claim_amount,
+        });
+    }
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount,
+        })
+        .add_attribute("action", "claim_deferred")
+        .add_attribute("recipient", info.sender))
+}
+
+/// ## Description
+/// Builds the message notifying the configured post-distribution hook contract, if any.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **info** is a reference to an object of type [`MessageInfo`].
+///
+/// - **amount** is an object of type [`Vec<Coin>`] which is the coins distributed.
+///
+/// - **num_recipients** is an object of type [`u32`] which is the number of recipients paid.
+fn post_hook_msg(
+    deps: Deps,
+    info: &MessageInfo,
+    amount: Vec<Coin>,
+    num_recipients: u32,
+) -> StdResult<Option<CosmosMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    let post_hook_contract = match config.post_hook_contract {
+        Some(post_hook_contract) => post_hook_contract,
+        None => return Ok(None),
+    };
+
+    Ok(Some(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: post_hook_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&PostHookMsg::DistributionSummary {
+            sender: info.sender.to_string(),
+            amount,
+            num_recipients,
+        })?,
+    })))
+}
+
+/// ## Description
+/// Removes the gas-reserve coin from the sent funds and builds the message refunding it to the
+/// sender. The contract spends none of the reserve, so the full attached amount is unused and
+/// returned.
+///
+/// ## Params
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is a reference to an object of type [`str`] which is the distributed denom.
+///
+/// - **gas_reserve_denom** is an [`Option`] of type [`String`] which is the denom of the gas-reserve coin.
+fn take_gas_reserve(
+    mut info: MessageInfo,
+    denom: &str,
+    gas_reserve_denom: Option<String>,
+) -> Result<(MessageInfo, Option<BankMsg>), ContractError> {
+    let gas_reserve_denom = match gas_reserve_denom {
+        Some(gas_reserve_denom) => gas_reserve_denom,
+        None => return Ok((info, None)),
+    };
+    if gas_reserve_denom == denom {
+        return Err(ContractError::InvalidGasReserveDenom {});
+    }
+
+    let (gas_reserve, funds): (Vec<Coin>, Vec<Coin>) = info
+        .funds
+        .into_iter()
+        .partition(|coin| coin.denom == gas_reserve_denom);
+    info.funds = funds;
+    let refund_msg = if gas_reserve.is_empty() {
+        None
+    } else {
+        Some(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: gas_reserve,
+        })
+    };
+    Ok((info, refund_msg))
+}
+
+/// ## Description
+/// Returns the amount of `denom` sent with the message. Exactly one coin of `denom` must be
+/// sent; empty funds, extra coins or any other denom are rejected.
+///
+/// ## Params
+/// - **info** is a reference to an object of type [`MessageInfo`].
+///
+/// - **denom** is a reference to an object of type [`str`] which is the expected denomination.
+fn sent_native_amount(info: &MessageInfo, denom: &str) -> Result<Uint128, ContractError> {
+    match info.funds.as_slice() {
+        [] => Err(ContractError::NoFundsSent {}),
+        [coin] if coin.denom == denom => Ok(coin.amount),
+        _ => Err(ContractError::MismatchedAssetType {}),
+    }
+}
+
+/// ## Description
+/// Returns a `display_amount` event per recipient, carrying both the base-unit amount and the
+/// amount formatted with `decimals` decimal places.
+///
+/// ## Params
+/// - **recipients** is a reference to an object of type [`Vec<Recipient>`] which is the list of recipients.
+///
+/// - **decimals** is an object of type [`u8`] which is the number of decimals of the distributed denom.
+fn display_amount_events(recipients: &[Recipient], decimals: u8) -> Vec<Event> {
+    recipients
+        .iter()
+        .map(|recipient| {
+            Event::new("display_amount")
+                .add_attribute("recipient", &recipient.recipient)
+                .add_attribute("amount", recipient.amount)
+                .add_attribute(
+                    "display_amount",
+                    format_decimals(recipient.amount, decimals),
+                )
+        })
+        .collect()
+}
+
+/// ## Description
+/// Formats a base-unit amount as a decimal string with `decimals` decimal places,
+/// e.g. `1234567` with 6 decimals is formatted as `1.234567`.
+///
+/// ## Params
+/// - **amount** is an object of type [`Uint128`] which is the base-unit amount.
+///
+/// - **decimals** is an object of type [`u8`] which is the number of decimal places.
+fn format_decimals(amount: Uint128, decimals: u8) -> String {
+    let digits = format!("{:0>width$}", amount.u128(), width = decimals as usize + 1);
+    if decimals == 0 {
+        return digits;
+    }
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    format!("{}.{}", whole, fraction)
+}
+
+/// ## Description
+/// Splits `amount` between addresses proportional to their weight. Each share is computed as
+/// `amount * weight / total_weight` and truncated, then rounded down to a multiple of
+/// `precision` if one is set; the remainder (dust) is added to the last address so the full
+/// amount is always distributed. A zero `precision` disables rounding. Addresses whose share
+/// ends up zero are left out.
+///
+/// ## Params
+/// - **amount** is an object of type [`Uint128`] which is the total amount to split.
+///
+/// - **weights** is an object of type [`Vec<(String, Uint128)>`] which is the list of addresses and their weight.
+///
+/// - **precision** is an [`Option`] of type [`Uint128`] which is the multiple each share is rounded down to.
+fn split_by_weight(
+    amount: Uint128,
+    weights: Vec<(String, Uint128)>,
+    precision: Option<Uint128>,
+) -> Result<Vec<Recipient>, ContractError> {
+    if weights.is_empty() {
+        return Err(ContractError::EmptyRecipients {});
+    }
+    let total_weight = weights
+        .iter()
+        .try_fold(Uint128::zero(), |sum, (_, weight)| sum.checked_add(*weight))
+        .map_err(StdError::from)?;
+    if total_weight.is_zero() {
+        return Err(ContractError::ZeroTotalWeight {});
+    }
+
+    let mut recipients: Vec<Recipient> = weights
+        .into_iter()
+        .map(|(recipient, weight)| {
+            let share = amount.multiply_ratio(weight, total_weight);
+            let amount = match precision {
+                Some(precision) if !precision.is_zero() => share - share % precision,
+                _ => share,
+            };
+            Recipient {
+                recipient,
+                amount,
+                deferred: false,
+            }
+        })
+        .collect();
+    let distributed: Uint128 = recipients
+        .iter()
+        .fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
+    if let Some(last) = recipients.last_mut() {
+        last.amount += amount - distributed;
+    }
+    recipients.retain(|recipient| !recipient.amount.is_zero());
+    Ok(recipients)
+}
+
+/// ## Description
+/// Splits `amount` by weight like [`split_by_weight`], then drops every recipient whose amount
+/// is below their `min_accept` and splits again between the rest, until every remaining
+/// recipient accepts their amount. Returns the amounts and the addresses that were dropped.
+///
+/// ## Params
+/// - **amount** is an object of type [`Uint128`] which is the total amount to split.
+///
+/// - **recipients** is an object of type [`Vec<WeightedRecipient>`] which is the list of recipient address, weight and minimum.
+///
+/// - **precision** is an [`Option`] of type [`Uint128`] which is the multiple each share is rounded down to.
+fn split_with_minimums(
+    amount: Uint128,
+    mut recipients: Vec<WeightedRecipient>,
+    precision: Option<Uint128>,
+) -> Result<(Vec<Recipient>, Vec<String>), ContractError> {
+    let mut below_minimum: Vec<String> = vec![];
+    loop {
+        let weights = recipients
+            .iter()
+            .map(|recipient| (recipient.recipient.clone(), recipient.weight))
+            .collect();
+        let split = split_by_weight(amount, weights, precision)?;
+
+        let (accepted, rejected): (Vec<WeightedRecipient>, Vec<WeightedRecipient>) =
+            recipients.into_iter().partition(|recipient| {
+                let got = split
+                    .iter()
+                    .find(|share| share.recipient == recipient.recipient)
+                    .map(|share| share.amount)
+                    .unwrap_or_default();
+                !matches!(recipient.min_accept, Some(min) if got < min)
+            });
+        if rejected.is_empty() {
+            return Ok((split, below_minimum));
+        }
+        below_minimum.extend(rejected.into_iter().map(|recipient| recipient.recipient));
+        recipients = accepted;
+    }
+}
+
+/// ## Description
+/// Splits `amount` evenly between the addresses. Each share is `amount / len` truncated and the
+/// remainder (dust) goes to the last address so the full amount is always distributed. Duplicate
+/// addresses are rejected before splitting.
+///
+/// ## Params
+/// - **amount** is an object of type [`Uint128`] which is the total amount to split.
+///
+/// - **addresses** is an object of type [`Vec<String>`] which is the list of recipient addresses.
+fn split_equally(amount: Uint128, addresses: Vec<String>) -> Result<Vec<Recipient>, ContractError> {
+    if has_duplicate_address(addresses.iter().map(|address| address.as_str())) {
+        return Err(ContractError::DuplicateRecipient {});
+    }
+    split_by_weight(
+        amount,
+        addresses
+            .into_iter()
+            .map(|address| (address, Uint128::new(1)))
+            .collect(),
+        None,
+    )
+}
+
+/// ## Description
+/// Converts shares into the address and weight pairs used by [`split_by_weight`].
+///
+/// ## Params
+/// - **shares** is an object of type [`Vec<Share>`] which is the list of recipient address and weight.
+fn share_weights(shares: Vec<Share>) -> Vec<(String, Uint128)> {
+    shares
+        .into_iter()
+        .map(|share| (share.recipient, Uint128::from(share.weight)))
+        .collect()
+}
+
+/// ## Description
+/// Adds `amount` to the running total the recipient has received of `asset`.
+///
+/// ## Params
+/// - **storage** is a mutable reference to an object implementing [`Storage`].
+///
+/// - **recipient** is a reference to an object of type [`Addr`] which is the recipient address.
+///
+/// - **asset** is a reference to an object of type [`str`] which is the native denom or CW20 contract address.
+///
+/// - **amount** is an object of type [`Uint128`] which is the amount received.
+fn add_recipient_total(
+    storage: &mut dyn Storage,
+    recipient: &Addr,
+    asset: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    RECIPIENT_TOTALS.update(storage, (recipient, asset), |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+/// ## Description
+/// Returns [`ContractError::LifetimeCapExceeded`] if receiving `amount` would push the total the
+/// recipient has been allotted of `asset` over the configured lifetime cap, see
+/// [`allotted_total`].
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipient** is a reference to an object of type [`Addr`] which is the recipient address.
+///
+/// - **asset** is a reference to an object of type [`str`] which is the native denom or CW20 contract address.
+///
+/// - **amount** is an object of type [`Uint128`] which is the amount to receive.
+fn assert_lifetime_cap(
+    deps: Deps,
+    recipient: &Addr,
+    asset: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let cap = match CONFIG.load(deps.storage)?.lifetime_cap {
+        Some(cap) => cap,
+        None => return Ok(()),
+    };
+    let allotted = allotted_total(deps.storage, recipient, asset)?;
+    if allotted.checked_add(amount).map_err(StdError::from)? > cap {
+        return Err(ContractError::LifetimeCapExceeded {
+            recipient: recipient.to_string(),
+            cap,
+        });
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Returns the total the recipient has been allotted of `asset`: the amount received plus the
+/// amounts still deferred to them or held in their open escrows. Counting the pending amounts
+/// keeps a later claim from exceeding the lifetime cap.
+///
+/// ## Params
+/// - **storage** is a reference to an object implementing [`Storage`].
+///
+/// - **recipient** is a reference to an object of type [`Addr`] which is the recipient address.
+///
+/// - **asset** is a reference to an object of type [`str`] which is the native denom or CW20 contract address.
+fn allotted_total(storage: &dyn Storage, recipient: &Addr, asset: &str) -> StdResult<Uint128> {
+    let received = RECIPIENT_TOTALS
+        .may_load(storage, (recipient, asset))?
+        .unwrap_or_default();
+    let claimable = CLAIMABLE
+        .may_load(storage, (recipient, asset))?
+        .unwrap_or_default();
+    let escrowed = ESCROWED
+        .may_load(storage, (recipient, asset))?
+        .unwrap_or_default();
+    Ok(received.checked_add(claimable)?.checked_add(escrowed)?)
+}
+
+/// ## Description
+/// Records `amount` of `denom` as claimable by the recipient and reserves it in the contract.
+///
+/// ## Params
+/// - **storage** is a mutable reference to an object implementing [`Storage`].
+///
+/// - **recipient** is a reference to an object of type [`Addr`] which is the recipient address.
+///
+/// - **denom** is a reference to an object of type [`str`] which is the native denom.
+///
+/// - **amount** is an object of type [`Uint128`] which is the deferred amount.
+fn add_claimable(
+    storage: &mut dyn Storage,
+    recipient: &Addr,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    CLAIMABLE.update(storage, (recipient, denom), |claimable| -> StdResult<_> {
+        Ok(claimable.unwrap_or_default() + amount)
+    })?;
+    RESERVED.update(storage, denom, |reserved| -> StdResult<_> {
+        Ok(reserved.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+/// ## Description
+/// Returns the contract's balance of `denom` that is not reserved for deferred or escrowed
+/// recipients or failed best-effort transfers.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **env** is a reference to an object of type [`Env`].
+///
+/// - **denom** is a reference to an object of type [`str`] which is the native denom.
+fn free_balance(deps: Deps, env: &Env, denom: &str) -> StdResult<Uint128> {
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, denom)?
+        .amount;
+    let reserved = RESERVED.may_load(deps.storage, denom)?.unwrap_or_default();
+    Ok(balance.saturating_sub(reserved))
+}
+
+/// ## Description
+/// Sums coins per denom, dropping denoms whose total is zero. The result is sorted by denom.
+///
+/// ## Params
+/// - **coins** is an iterator over the coins to aggregate.
+fn aggregate_coins<'a>(coins: impl Iterator<Item = &'a Coin>) -> BTreeMap<String, Uint128> {
+    let mut totals: BTreeMap<String, Uint128> = BTreeMap::new();
+    for coin in coins {
+        *totals.entry(coin.denom.clone()).or_default() += coin.amount;
+    }
+    totals.retain(|_, amount| !amount.is_zero());
+    totals
+}
+
+/// ## Description
+/// Returns [`ContractError::Unauthorized`] unless `sender` is the contract owner.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **sender** is a reference to an object of type [`Addr`] which is the address to check.
+fn assert_owner(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if CONFIG.load(deps.storage)?.owner.as_ref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Returns whether `address` opted in to receiving distributions.
+///
+/// ## Params
+/// - **storage** is a reference to an object of type [`Storage`].
+///
+/// - **address** is a reference to an object of type [`Addr`] which is the address to check.
+fn has_opted_in(storage: &dyn Storage, address: &Addr) -> bool {
+    OPTED_IN.has(storage, address)
+}
+
+/// ## Description
+/// Returns [`ContractError::NotOptedIn`] if opt-in is required and one of the screened
+/// recipients did not opt in. Used by distributions that cannot defer a payout, e.g. of CW20
+/// tokens.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipients** is a reference to a slice of type [`Recipient`] which is the list of screened recipients.
+fn assert_opted_in(deps: Deps, recipients: &[Recipient]) -> Result<(), ContractError> {
+    if !CONFIG.load(deps.storage)?.require_opt_in {
+        return Ok(());
+    }
+    for recipient in recipients {
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+        if !has_opted_in(deps.storage, &recipient_addr) {
+            return Err(ContractError::NotOptedIn {
+                address: recipient.recipient.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Checks that `nonce`, if set, is the sender's next nonce and records it. A sender's first
+/// nonce is 0 and each following one must be exactly one higher, so reordered or replayed
+/// submissions are rejected.
+///
+/// ## Params
+/// - **storage** is a mutable reference to an object of type [`Storage`].
+///
+/// - **sender** is a reference to an object of type [`Addr`] which is the sender of the distribution.
+///
+/// - **nonce** is an [`Option`] of type [`u64`] which is the nonce supplied with the distribution.
+fn use_nonce(
+    storage: &mut dyn Storage,
+    sender: &Addr,
+    nonce: Option<u64>,
+) -> Result<(), ContractError> {
+    let got = match nonce {
+        Some(nonce) => nonce,
+        None => return Ok(()),
+    };
+    let expected = NONCES.may_load(storage, sender)?.map_or(0, |last| last + 1);
+    if got != expected {
+        return Err(ContractError::InvalidNonce { expected, got });
+    }
+    NONCES.save(storage, sender, &got)?;
+    Ok(())
+}
+
+/// ## Description
+/// Returns an event of type `ty` carrying the recipient address and amount, emitted once per
+/// recipient so indexers can follow distributions with standard event subscriptions.
+///
+/// ## Params
+/// - **ty** is a reference to an object of type [`str`] which is the event type.
+///
+/// - **recipient** is a reference to an object of type [`Recipient`] which is the recipient of the transfer.
+fn recipient_event(ty: &str, recipient: &Recipient) -> Event {
+    Event::new(ty)
+        .add_attribute("recipient", &recipient.recipient)
+        .add_attribute("amount", recipient.amount)
+}
+
+/// ## Description
+/// Adds the detail attributes of a distribution to the response, unless the event verbosity is
+/// [`EventLevel::Minimal`], which only reports the `action` attribute.
+///
+/// ## Params
+/// - **event_verbosity** is a reference to an object of type [`EventLevel`] which is the configured event verbosity.
+///
+/// - **response** is an object of type [`Response`] which is the response of the distribution.
+///
+/// - **attributes** is an object of type [`Vec<Attribute>`] which is the list of detail attributes.
+fn add_details(
+    event_verbosity: &EventLevel,
+    response: Response,
+    attributes: Vec<Attribute>,
+) -> Response {
+    if *event_verbosity == EventLevel::Minimal {
+        return response;
+    }
+    response.add_attributes(attributes)
+}
+
+/// ## Description
+/// Asks the configured approval hook, if any, whether the proposed distribution may execute and
+/// returns [`ContractError::NotApproved`] if it refuses.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **asset** is a reference to an object of type [`str`] which is the native denom or CW20 contract address to distribute.
+///
+/// - **recipients** is a reference to an object of type [`Vec<Recipient>`] which is the list of recipient address and amount.
+fn assert_approved(deps: Deps, asset: &str, recipients: &[Recipient]) -> Result<(), ContractError> {
+    let approval_hook = match CONFIG.load(deps.storage)?.approval_hook {
+        Some(approval_hook) => approval_hook,
+        None => return Ok(()),
+    };
+    let approval: ApprovalResponse = deps.querier.query_wasm_smart(
+        approval_hook,
+        &ApprovalQueryMsg::Approve {
+            asset: asset.to_string(),
+            recipients: recipients.to_vec(),
+        },
+    )?;
+    if !approval.approved {
+        return Err(ContractError::NotApproved {});
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Asks the configured approval hook, if any, to approve a multi-asset distribution, every denom
+/// as its own plan, see [`assert_approved`].
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipients** is a reference to a slice of type [`MultiRecipient`] which is the list of recipient address and coins.
+fn assert_multi_asset_approved(
+    deps: Deps,
+    recipients: &[MultiRecipient],
+) -> Result<(), ContractError> {
+    if CONFIG.load(deps.storage)?.approval_hook.is_none() {
+        return Ok(());
+    }
+    let denoms = aggregate_coins(
+        recipients
+            .iter()
+            .flat_map(|recipient| recipient.coins.iter()),
+    );
+    for denom in denoms.keys() {
+        let plan: Vec<Recipient> = recipients
+            .iter()
+            .filter_map(|recipient| {
+                let amount = aggregate_coins(recipient.coins.iter()).remove(denom)?;
+                Some(Recipient {
+                    recipient: recipient.recipient.clone(),
+                    amount,
+                    deferred: false,
+                })
+            })
+            .collect();
+        assert_approved(deps, denom, &plan)?;
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Returns [`ContractError::TooManyRecipients`] if `got` exceeds the configured maximum number
+/// of recipients per distribution.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **got** is an object of type [`usize`] which is the number of recipients of the distribution.
+fn assert_max_recipients(deps: Deps, got: usize) -> Result<(), ContractError> {
+    if let Some(max) = CONFIG.load(deps.storage)?.max_recipients {
+        if got > max as usize {
+            return Err(ContractError::TooManyRecipients {
+                max,
+                got: got as u32,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Rejects an empty recipient list and any recipient with a zero amount, so every accepted
+/// distribution moves tokens.
+///
+/// ## Params
+/// - **recipients** is a reference to an object of type [`Vec<Recipient>`] which is the list of recipients to check.
+fn validate_recipient_amounts(recipients: &[Recipient]) -> Result<(), ContractError> {
+    if recipients.is_empty() {
+        return Err(ContractError::EmptyRecipients {});
+    }
+    if recipients
+        .iter()
+        .any(|recipient| recipient.amount.is_zero())
+    {
+        return Err(ContractError::ZeroAmount {});
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Returns true if any address appears more than once, regardless of position or amount.
+///
+/// ## Params
+/// - **addresses** is an [`Iterator`] of type [`str`] which is the list of recipient addresses.
+fn has_duplicate_address<'a>(addresses: impl Iterator<Item = &'a str>) -> bool {
+    let mut seen = HashSet::new();
+    addresses.into_iter().any(|address| !seen.insert(address))
+}
+
+/// ## Description
+/// Returns whether `address` has the shape of the given format. Bech32 addresses must be
+/// lowercase with a human-readable part, the `1` separator and at least 6 data characters from
+/// the bech32 charset; the checksum itself is left to the chain's address validation.
+///
+/// ## Params
+/// - **address** is a reference to an object of type [`str`] which is the address to check.
+///
+/// - **format** is a reference to an object of type [`AddressFormat`] which is the expected format.
+fn matches_address_format(address: &str, format: &AddressFormat) -> bool {
+    const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    match format {
+        AddressFormat::Any => true,
+        AddressFormat::Ethereum => {
+            address.len() == 42
+                && address.starts_with("0x")
+                && address[2..].chars().all(|c| c.is_ascii_hexdigit())
+        }
+        AddressFormat::Bech32 => match address.rsplit_once('1') {
+            Some((hrp, data)) => {
+                address.len() <= 90
+                    && !hrp.is_empty()
+                    && hrp
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+                    && data.len() >= 6
+                    && data.chars().all(|c| BECH32_CHARSET.contains(c))
+            }
+            None => false,
+        },
+    }
+}
+
+/// ## Description
+/// Returns whether the owner froze `address` from receiving distributions.
+///
+/// ## Params
+/// - **storage** is a reference to an object of type [`Storage`].
+///
+/// - **address** is a reference to an object of type [`Addr`] which is the address to check.
+fn is_frozen(storage: &dyn Storage, address: &Addr) -> bool {
+    FROZEN.has(storage, address)
+}
+
+/// ## Description
+/// This enumeration describes how [`screen_recipient`] handles a recipient address.
+enum Screening {
+    /// The recipient is paid
+    Keep,
+    /// The recipient is skipped
+    Skip,
+    /// The recipient's amount goes to the fallback recipient
+    Redirect(Addr),
+}
+
+/// ## Description
+/// This structure describes the outcome of [`filter_recipients`] and
+/// [`filter_multi_recipients`].
+struct FilteredRecipients<T = Recipient> {
+    /// Recipients to distribute to
+    kept: Vec<T>,
+    /// Addresses that were skipped
+    skipped: Vec<String>,
+    /// Amounts redirected to the fallback recipient as `address:amount` pairs
+    redirected: Vec<String>,
+}
+
+/// ## Description
+/// Validates a recipient address, including the configured address format, according to the
+/// failure policy. If a fallback recipient is configured, an invalid address is redirected to it
+/// regardless of the failure policy. A valid address that the owner froze or, if a policy
+/// contract is configured, that the policy contract denies is handled according to the deny
+/// policy.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **config** is a reference to an object of type [`Config`] which is the contract configuration.
+///
+/// - **address** is a reference to an object of type [`str`] which is the recipient address.
+///
+/// - **failure_policy** is a reference to an object of type [`FailurePolicy`] which decides how invalid recipient addresses are handled.
+fn screen_recipient(
+    deps: Deps,
+    config: &Config,
+    address: &str,
+    failure_policy: &FailurePolicy,
+) -> Result<Screening, ContractError> {
+    let validated = if matches_address_format(address, &config.address_format) {
+        deps.api.addr_validate(address).map_err(ContractError::from)
+    } else {
+        Err(ContractError::InvalidAddressFormat {
+            address: address.to_string(),
+        })
+    };
+    let recipient_addr = match validated {
+        Ok(recipient_addr) => recipient_addr,
+        Err(err) => {
+            return match (&config.fallback_recipient, failure_policy) {
+                (Some(fallback), _) => Ok(Screening::Redirect(fallback.clone())),
+                (None, FailurePolicy::AbortAll) => Err(err),
+                (None, FailurePolicy::SkipInvalid) => Ok(Screening::Skip),
+            }
+        }
+    };
+
+    // frozen recipients are handled like recipients denied by the policy contract
+    if is_frozen(deps.storage, &recipient_addr) {
+        return match config.deny_policy {
+            DenyPolicy::Reject => Err(ContractError::RecipientFrozen {
+                address: address.to_string(),
+            }),
+            DenyPolicy::Drop => Ok(Screening::Skip),
+        };
+    }
+
+    // ask the policy contract whether the recipient may receive funds
+    if let Some(policy_contract) = &config.policy_contract {
+        let check: CheckResponse = deps.querier.query_wasm_smart(
+            policy_contract,
+            &PolicyQueryMsg::Check {
+                address: address.to_string(),
+            },
+        )?;
+        if !check.allowed {
+            return match config.deny_policy {
+                DenyPolicy::Reject => Err(ContractError::RecipientDenied {
+                    recipient: address.to_string(),
+                }),
+                DenyPolicy::Drop => Ok(Screening::Skip),
+            };
+        }
+    }
+    Ok(Screening::Keep)
+}
+
+/// ## Description
+/// Screens every recipient with [`screen_recipient`]. Returns the recipients to distribute to,
+/// the addresses that were skipped and the redirected amounts as `address:amount` pairs. The
+/// redirected amounts are merged into the fallback recipient's entry.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipients to validate.
+///
+/// - **failure_policy** is an object of type [`FailurePolicy`] which decides how invalid recipient addresses are handled.
+fn filter_recipients(
+    deps: Deps,
+    recipients: Vec<Recipient>,
+    failure_policy: &FailurePolicy,
+) -> Result<FilteredRecipients, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut kept: Vec<Recipient> = vec![];
+    let mut skipped: Vec<String> = vec![];
+    let mut redirected: Vec<String> = vec![];
+    let mut redirected_amount = Uint128::zero();
+    for recipient in recipients {
+        match screen_recipient(deps, &config, &recipient.recipient, failure_policy)? {
+            Screening::Keep => kept.push(recipient),
+            Screening::Skip => skipped.push(recipient.recipient),
+            Screening::Redirect(_) => {
+                redirected.push(format!("{}:{}", recipient.recipient, recipient.amount));
+                redirected_amount = redirected_amount
+                    .checked_add(recipient.amount)
+                    .map_err(StdError::from)?;
+            }
+        }
+    }
+
+    // merge into the fallback's own entry so the list stays free of duplicates
+    if let Some(fallback) = config.fallback_recipient {
+        if !redirected_amount.is_zero() {
+            match kept
+                .iter_mut()
+                .find(|recipient| recipient.recipient == fallback.as_str())
+            {
+                Some(existing) => {
+                    existing.amount = existing
+                        .amount
+                        .checked_add(redirected_amount)
+                        .map_err(StdError::from)?
+                }
+                None => kept.push(Recipient {
+                    recipient: fallback.to_string(),
+                    amount: redirected_amount,
+                    deferred: false,
+                }),
+            }
+        }
+    }
+    Ok(FilteredRecipients {
+        kept,
+        skipped,
+        redirected,
+    })
+}
+
+/// ## Description
+/// Screens every recipient of a multi-asset distribution like [`filter_recipients`], aborting on
+/// an invalid address unless a fallback recipient is configured. Redirected amounts are reported
+/// per coin.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipients** is an object of type [`Vec<MultiRecipient>`] which is the list of recipients to validate.
+fn filter_multi_recipients(
+    deps: Deps,
+    recipients: Vec<MultiRecipient>,
+) -> Result<FilteredRecipients<MultiRecipient>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut kept: Vec<MultiRecipient> = vec![];
+    let mut skipped: Vec<String> = vec![];
+    let mut redirected: Vec<String> = vec![];
+    let mut redirected_coins: Vec<Coin> = vec![];
+    for recipient in recipients {
+        match screen_recipient(
+            deps,
+            &config,
+            &recipient.recipient,
+            &FailurePolicy::AbortAll,
+        )? {
+            Screening::Keep => kept.push(recipient),
+            Screening::Skip => skipped.push(recipient.recipient),
+            Screening::Redirect(_) => {
+                for coin in recipient.coins.iter() {
+                    redirected.push(format!("{}:{}", recipient.recipient, coin));
+                }
+                redirected_coins.extend(recipient.coins);
+            }
+        }
+    }
+
+    // merge into the fallback's own entry so the list stays free of duplicates
+    if let Some(fallback) = config.fallback_recipient {
+        if !redirected_coins.is_empty() {
+            match kept
+                .iter_mut()
+                .find(|recipient| recipient.recipient == fallback.as_str())
+            {
+                Some(existing) => existing.coins.extend(redirected_coins),
+                None => kept.push(MultiRecipient {
+                    recipient: fallback.to_string(),
+                    coins: redirected_coins,
+                }),
+            }
+        }
+    }
+    Ok(FilteredRecipients {
+        kept,
+        skipped,
+        redirected,
+    })
+}
+
+/// ## Description
+/// Handles the replies of submessages. The wrap of a wrapped distribution replies with
+/// `WRAP_REPLY_ID`, see [`reply_wrap`], the last swap of a dust sweep with `SWAP_REPLY_ID`,
+/// see [`reply_swap`], the vault withdrawal of a distribution paid from the contract's balance
+/// with `VAULT_REPLY_ID`, see [`reply_vault`], the unwrap of a CW20 distribution paying native
+/// coins with `UNWRAP_REPLY_ID`, see [`reply_unwrap`], and the swap of a swapped distribution
+/// with `SWAP_DISTRIBUTION_REPLY_ID`, see [`reply_swap_distribution`]. Failed transfers of a best-effort native distribution
+/// or a non-atomic multi-asset distribution reply with the index of the failed transfer: the
+/// failed amount is removed from the recipient's total and is reported in the
+/// `failed_recipient`, `failed_denom` and `failed_amount` attributes. It is sent to the treasury
+/// fallback if one is configured, otherwise it stays in the contract for the sender to reclaim.
+/// Successful multi-asset transfers are reported in the `succeeded_recipient`,
+/// `succeeded_denom` and `succeeded_amount` attributes.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **msg** is an object of type [`Reply`] whose id is the index of the failed transfer, offset
+///   by `MULTI_ASSET_REPLY_OFFSET` for multi-asset transfers.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id == WRAP_REPLY_ID {
+        return reply_wrap(deps, env);
+    }
+    if msg.id == SWAP_REPLY_ID {
+        return reply_swap(deps, env);
+    }
+    if msg.id == VAULT_REPLY_ID {
+        return reply_vault(deps, env);
+    }
+    if msg.id == UNWRAP_REPLY_ID {
+        return reply_unwrap(deps, env);
+    }
+    if msg.id == SWAP_DISTRIBUTION_REPLY_ID {
+        return reply_swap_distribution(deps, env);
+    }
+
+    let error = match msg.result {
+        SubMsgResult::Ok(_) if msg.id >= MULTI_ASSET_REPLY_OFFSET => {
+            let (_, recipient_addr, coin) = multi_asset_transfer(deps.storage, msg.id)?;
+            return Ok(Response::new()
+                .add_attribute("succeeded_recipient", recipient_addr)
+                .add_attribute("succeeded_denom", coin.denom)
+                .add_attribute("succeeded_amount", coin.amount));
+        }
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+        SubMsgResult::Err(error) => error,
+    };
+
+    let (sender, recipient_addr, coin) = if msg.id >= MULTI_ASSET_REPLY_OFFSET {
+        multi_asset_transfer(deps.storage, msg.id)?
+    } else {
+        let batch = BEST_EFFORT_BATCH.load(deps.storage)?;
+        let recipient = batch
+            .recipients
+            .get(msg.id as usize)
+            .ok_or_else(|| StdError::not_found("best effort recipient"))?;
+        (
+            batch.sender.clone(),
+            deps.api.addr_validate(&recipient.recipient)?,
+            Coin {
+                denom: batch.denom.clone(),
+                amount: recipient.amount,
+            },
+        )
+    };
+    RECIPIENT_TOTALS.update(
+        deps.storage,
+        (&recipient_addr, &coin.denom),
+        |total| -> StdResult<_> { Ok(total.unwrap_or_default().checked_sub(coin.amount)?) },
+    )?;
+
+    let mut response = Response::new()
+        .add_attribute("failed_recipient", &recipient_addr)
+        .add_attribute("failed_denom", &coin.denom)
+        .add_attribute("failed_amount", coin.amount)
+        .add_attribute("error", error);
+    match CONFIG.load(deps.storage)?.treasury_fallback {
+        Some(treasury) => {
+            response = response
+                .add_message(BankMsg::Send {
+                    to_address: treasury.to_string(),
+                    amount: vec![coin],
+                })
+                .add_attribute("fallback_recipient", treasury);
+        }
+        None => {
+            RECLAIMABLE.update(
+                deps.storage,
+                (&sender, &coin.denom),
+                |reclaimable| -> StdResult<_> {
+                    Ok(reclaimable.unwrap_or_default().checked_add(coin.amount)?)
+                },
+            )?;
+            RESERVED.update(deps.storage, &coin.denom, |reserved| -> StdResult<_> {
+                Ok(reserved.unwrap_or_default().checked_add(coin.amount)?)
+            })?;
+        }
+    }
+    Ok(response)
+}
+
+/// ## Description
+/// Returns the sender, recipient and coin of the non-atomic multi-asset transfer replying with
+/// `id`.
+///
+/// ## Params
+/// - **storage** is a reference to an object of type [`Storage`].
+///
+/// - **id** is an object of type [`u64`] which is the reply id of the transfer.
+fn multi_asset_transfer(storage: &dyn Storage, id: u64) -> StdResult<(Addr, Addr, Coin)> {
+    let batch = MULTI_ASSET_BATCH.load(storage)?;
+    let (recipient_addr, coin) = batch
+        .transfers
+        .get((id - MULTI_ASSET_REPLY_OFFSET) as usize)
+        .cloned()
+        .ok_or_else(|| StdError::not_found("multi asset transfer"))?;
+    Ok((batch.sender, recipient_addr, coin))
+}
+
+/// ## Description
+/// Handles the reply of the wrapper contract of a wrapped distribution, see
+/// [`try_distribute_wrapped`]. The wrapped amount must equal the recipients' total, which is then
+/// distributed as CW20 transfers.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+fn reply_wrap(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let pending = PENDING_WRAP.load(deps.storage)?;
+    PENDING_WRAP.remove(deps.storage);
+    let balance: BalanceResponse = deps.querier.query_wasm_smart(
+        &pending.wrapper,
+        &Cw20QueryMsg::Balance {
+            address: env.contract.address.to_string(),
+        },
+    )?;
+    let wrapped = balance.balance.saturating_sub(pending.balance_before);
+    let sum_recipient_amount: Uint128 = pending
+        .recipients
+        .iter()
+        .fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
+    if wrapped != sum_recipient_amount {
+        return Err(ContractError::UnexpectedWrappedAmount {
+            expected: sum_recipient_amount,
+            got: wrapped,
+        });
+    }
+
+    let mut transfer_msgs: Vec<CosmosMsg> = vec![];
+    for recipient in pending.recipients.into_iter() {
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+        add_recipient_total(
+            deps.storage,
+            &recipient_addr,
+            pending.wrapper.as_str(),
+            recipient.amount,
+        )?;
+
+        transfer_msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: pending.wrapper.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.recipient,
+                amount: recipient.amount,
+            })?,
+        }))
+    }
+
+    Ok(Response::new()
+        .add_messages(transfer_msgs)
+        .add_attribute("wrapped_amount", wrapped))
+}
+
+/// ## Description
+/// Handles the reply of the last swap of a dust sweep, see [`try_sweep_dust_to_asset`]. The
+/// proceeds are the growth of the contract's balance of the target denom and are sent to the
+/// owner who started the sweep.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+fn reply_swap(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let pending = PENDING_SWAP.load(deps.storage)?;
+    PENDING_SWAP.remove(deps.storage);
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &pending.target_denom)?;
+    let proceeds = balance.amount.saturating_sub(pending.balance_before);
+
+    let mut response = Response::new().add_attribute("proceeds", proceeds);
+    if !proceeds.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: pending.recipient.to_string(),
+            amount: coins(proceeds.u128(), pending.target_denom),
+        });
+    }
+    Ok(response)
+}
+
+/// ## Description
+/// Handles the reply of the vault contract covering the shortfall of a distribution paid from
+/// the contract's balance, see [`try_distribute_from_balance`]. The free balance must now cover
+/// the recipients, which are then paid.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+fn reply_vault(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let pending = PENDING_VAULT.load(deps.storage)?;
+    PENDING_VAULT.remove(deps.storage);
+    let required = pending
+        .recipients
+        .iter()
+        .try_fold(Uint128::zero(), |sum, recipient| {
+            sum.checked_add(recipient.amount)
+        })
+        .map_err(StdError::from)?;
+    let available = free_balance(deps.as_ref(), &env, &pending.denom)?;
+    if available < required {
+        return Err(ContractError::VaultShortfall {
+            denom: pending.denom,
+            shortfall: required - available,
+        });
+    }
+
+    distribute_from_balance(
+        deps,
+        pending.sender,
+        pending.denom,
+        pending.recipients,
+        required,
+    )
+}
+
+/// ## Description
+/// Handles the reply of the unwrap adapter of a CW20 distribution paying native coins, see
+/// [`try_distribute_unwrapped`]. The unwrapped amount must equal the tokens sent to the adapter,
+/// which are then distributed as native coins.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+fn reply_unwrap(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let pending = PENDING_UNWRAP.load(deps.storage)?;
+    PENDING_UNWRAP.remove(deps.storage);
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &pending.denom)?;
+    let unwrapped = balance.amount.saturating_sub(pending.balance_before);
+    if unwrapped != pending.amount {
+        return Err(ContractError::UnexpectedUnwrappedAmount {
+            expected: pending.amount,
+            got: unwrapped,
+        });
+    }
+
+    let unwrapped_info = MessageInfo {
+        sender: pending.sender,
+        funds: coins(unwrapped.u128(), pending.denom.clone()),
+    };
+    let response = try_distribute_native(
+        deps,
+        unwrapped_info,
+        pending.denom,
+        pending.recipients,
+        pending.failure_policy,
+        false,
+        None,
+    )?;
+    Ok(response.add_attribute("unwrapped_amount", unwrapped))
+}
+
+/// ## Description
+/// Handles the reply of the swap of a swapped distribution, see [`try_distribute_swapped`]. The
+/// proceeds are the growth of the contract's balance of the denom and must be at least the
+/// minimum output. They are split by weight with the same dust handling as the other weighted
+/// distributions and distributed.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+fn reply_swap_distribution(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let pending = PENDING_SWAP_DISTRIBUTION.load(deps.storage)?;
+    PENDING_SWAP_DISTRIBUTION.remove(deps.storage);
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &pending.denom)?;
+    let proceeds = balance.amount.saturating_sub(pending.balance_before);
+    if proceeds < pending.min_output {
+        return Err(ContractError::SlippageExceeded {
+            min: pending.min_output,
+            got: proceeds,
+        });
+    }
+
+    let (recipients, below_minimum) = split_with_minimums(proceeds, pending.recipients, None)?;
+    let proceeds_info = MessageInfo {
+        sender: pending.sender,
+        funds: coins(proceeds.u128(), pending.denom.clone()),
+    };
+    let event_verbosity = CONFIG.load(deps.storage)?.event_verbosity;
+    let response = try_distribute_native(
+        deps,
+        proceeds_info,
+        pending.denom,
+        recipients,
+        FailurePolicy::AbortAll,
+        false,
+        None,
+    )?;
+    let mut details = vec![attr("proceeds", proceeds)];
+    if !below_minimum.is_empty() {
+        details.push(attr("below_minimum", below_minimum.join(",")));
+    }
+    Ok(add_details(&event_verbosity, response, details))
+}
+
+/// ## Description
+/// Exposes all the queries available in the contract.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **msg** is an object of type [`QueryMsg`].
+///
+/// ## Queries
+/// - **QueryMsg::ValidateBatch { message }** Returns whether a distribution message would pass
+///   validation and the native coins it must be sent with.
+///
+/// - **QueryMsg::Config {}** Returns the contract name, version and configuration.
+///
+/// - **QueryMsg::NormalizeWeights {
+///   recipients,
+///   target_total,
+///   precision,
+///   }** Returns the amounts the recipients would receive if `target_total` were split by weight.
+///
+/// - **QueryMsg::RecipientTotal { address, asset }** Returns the total amount an address has
+///   received across all distributions.
+///
+/// - **QueryMsg::ClaimableBalance { recipient }** Returns the native balances deferred to a
+///   recipient.
+///
+/// - **QueryMsg::Shortfall { asset, recipients }** Compares the amount needed to pay the
+///   recipients against the contract's free balance.
+///
+/// - **QueryMsg::AllParams {}** Returns every config parameter and its current value.
+///
+/// - **QueryMsg::TotalEscrowed { denom }** Returns the native amounts held on behalf of deferred
+///   recipients, escrowed recipients and senders of failed best-effort transfers.
+///
+/// - **QueryMsg::DecodeHook { msg }** Decodes a CW20 hook message.
+///
+/// - **QueryMsg::StorageEstimate { recipients, escrowed }** Estimates the bytes a distribution
+///   would persist.
+///
+/// - **QueryMsg::CanAfford { asset, amount }** Returns whether the contract's free balance covers
+///   an amount.
+///
+/// - **QueryMsg::ReleaseForecast {
+///   denom,
+///   horizon_seconds,
+///   buckets,
+///   }** Returns the escrowed amounts becoming claimable within a horizon.
+///
+/// - **QueryMsg::HasOptedIn { address }** Returns whether an address has opted in.
+///
+/// - **QueryMsg::IsFrozen { address }** Returns whether an address is frozen.
+///
+/// - **QueryMsg::MessageSchema {}** Returns the supported execute messages and their required
+///   fields.
+///
+/// - **QueryMsg::DiffRecipients { old, new }** Returns the recipients added, removed and changed
+///   between two recipient lists.
+///
+/// - **QueryMsg::SuggestPageSize {
+///   recipient_count,
+///   gas_target,
+///   }** Suggests how to split a large distribution into calls under a gas target.
+///
+/// - **QueryMsg::SimulateBatch { operations }** Returns which of a sequence of hypothetical
+///   distributions would fail.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::ValidateBatch { message } => to_binary(&query_validate_batch(deps, message)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::NormalizeWeights {
+            recipients,
+            target_total,
+            precision,
+        } => to_binary(&query_normalize_weights(
+            deps,
+            recipients,
+            target_total,
+            precision,
+        )?),
+        QueryMsg::RecipientTotal { address, asset } => {
+            to_binary(&query_recipient_total(deps, address, asset)?)
+        }
+        QueryMsg::ClaimableBalance { recipient } => {
+            to_binary(&query_claimable_balance(deps, recipient)?)
+        }
+        QueryMsg::Shortfall { asset, recipients } => {
+            to_binary(&query_shortfall(deps, env, asset, recipients)?)
+        }
+        QueryMsg::AllParams {} => to_binary(&query_all_params(deps)?),
+        QueryMsg::TotalEscrowed { denom } => to_binary(&query_total_escrowed(deps, denom)?),
+        QueryMsg::DecodeHook { msg } => to_binary(&query_decode_hook(deps, msg)?),
+        QueryMsg::StorageEstimate {
+            recipients,
+            escrowed,
+        } => to_binary(&query_storage_estimate(deps, recipients, escrowed)?),
+        QueryMsg::CanAfford { asset, amount } => {
+            to_binary(&query_can_afford(deps, env, asset, amount)?)
+        }
+        QueryMsg::ReleaseForecast {
+            denom,
+            horizon_seconds,
+            buckets,
+        } => to_binary(&query_release_forecast(
+            deps,
+            env,
+            denom,
+            horizon_seconds,
+            buckets,
+        )?),
+        QueryMsg::HasOptedIn { address } => to_binary(&query_has_opted_in(deps, address)?),
+        QueryMsg::IsFrozen { address } => to_binary(&query_is_frozen(deps, address)?),
+        QueryMsg::MessageSchema {} => to_binary(&query_message_schema()),
+        QueryMsg::DiffRecipients { old, new } => to_binary(&query_diff_recipients(old, new)),
+        QueryMsg::SuggestPageSize {
+            recipient_count,
+            gas_target,
+        } => to_binary(&query_suggest_page_size(deps, recipient_count, gas_target)?),
+        QueryMsg::SimulateBatch { operations } => {
+            to_binary(&query_simulate_batch(deps, env, operations)?)
+        }
+    }
+}
+
+/// ## Description
+/// Returns whether the distribution `message` would pass validation, without executing it, and
+/// the native coins it must be sent with.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **message** is an object of type [`ExecuteMsg`] which is the distribution to validate.
+pub fn query_validate_batch(deps: Deps, message: ExecuteMsg) -> StdResult<ValidateBatchResponse> {
+    Ok(match validate_batch(deps, message) {
+        Ok(required_funds) => ValidateBatchResponse {
+            valid: true,
+            error: None,
+            required_funds,
+        },
+        Err(err) => ValidateBatchResponse {
+            valid: false,
+            error: Some(err.to_string()),
+            required_funds: vec![],
+        },
+    })
+}
+
+/// ## Description
+/// Runs the checks the distribution `message` runs before building its transfers and returns the
+/// native coins it must be sent with. CW20 distributions carry their amount in the message and
+/// require no coins. Only distributions with fixed recipient amounts can be validated.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **message** is an object of type [`ExecuteMsg`] which is the distribution to validate.
+fn validate_batch(deps: Deps, message: ExecuteMsg) -> Result<Vec<Coin>, ContractError> {
+    match message {
+        ExecuteMsg::Receive(cw20_msg) => match from_binary(&cw20_msg.msg) {
+            Ok(Cw20HookMsg::DistributeCw20 {
+                asset_token,
+                recipients,
+                failure_policy,
+                output,
+                ..
+            }) => {
+                let total = match output {
+                    OutputKind::SameToken => {
+                        if recipients.iter().any(|recipient| recipient.deferred) {
+                            return Err(ContractError::UnsupportedDeferred {});
+                        }
+                        check_batch_recipients(
+                            deps,
+                            &asset_token,
+                            recipients,
+                            &failure_policy,
+                            false,
+                        )?
+                    }
+                    OutputKind::Native(denom) => {
+                        if CONFIG.load(deps.storage)?.unwrap_adapter.is_none() {
+                            return Err(ContractError::Generic(
+                                "no unwrap adapter configured".to_string(),
+                            ));
+                        }
+                        let total = check_batch_recipients(
+                            deps,
+                            &denom,
+                            recipients,
+                            &failure_policy,
+                            true,
+                        )?;
+                        check_min_total(deps, total)?;
+                        total
+                    }
+                };
+                if cw20_msg.amount != total {
+                    return Err(ContractError::MismatchedAssetAmount {});
+                }
+                Ok(vec![])
+            }
+            Ok(Cw20HookMsg::DistributeCw20ByShares {
+                asset_token,
+                shares,
+            }) => {
+                let recipients = split_by_weight(cw20_msg.amount, share_weights(shares), None)?;
+                check_batch_recipients(
+                    deps,
+                    &asset_token,
+                    recipients,
+                    &FailurePolicy::AbortAll,
+                    false,
+                )?;
+                Ok(vec![])
+            }
+            Ok(Cw20HookMsg::DistributeCw20Equal {
+                asset_token,
+                recipients,
+            }) => {
+                let recipients = split_equally(cw20_msg.amount, recipients)?;
+                check_batch_recipients(
+                    deps,
+                    &asset_token,
+                    recipients,
+                    &FailurePolicy::AbortAll,
+                    false,
+                )?;
+                Ok(vec![])
+            }
+            Err(_) => Err(ContractError::Generic(
+                "invalid cw20 hook message".to_string(),
+            )),
+        },
+        ExecuteMsg::DistributeNative {
+            denom,
+            recipients,
+            failure_policy,
+            ..
+        } => {
+            let total = check_batch_recipients(deps, &denom, recipients, &failure_policy, true)?;
+            check_min_total(deps, total)?;
+            Ok(coins(total.u128(), denom))
+        }
+        ExecuteMsg::DistributeLinear {
+            denom,
+            recipients,
+            top_amount,
+            bottom_amount,
+        } => {
+            let recipients = linear_amounts(recipients, top_amount, bottom_amount);
+            let total =
+                check_batch_recipients(deps, &denom, recipients, &FailurePolicy::AbortAll, true)?;
+            check_min_total(deps, total)?;
+            Ok(coins(total.u128(), denom))
+        }
+        ExecuteMsg::DistributeMultiAsset { recipients, .. } => {
+            let recipients = filter_multi_recipients(deps, recipients)?.kept;
+            let required = aggregate_coins(
+                recipients
+                    .iter()
+                    .flat_map(|recipient| recipient.coins.iter()),
+            );
+            if let Some(limit) = CONFIG.load(deps.storage)?.max_denoms_per_tx {
+                let got = required.len() as u32;
+                if got > limit {
+                    return Err(ContractError::TooManyDenoms { limit, got });
+                }
+            }
+            if has_duplicate_address(
+                recipients
+                    .iter()
+                    .map(|recipient| recipient.recipient.as_str()),
+            ) {
+                return Err(ContractError::DuplicateRecipient {});
+            }
+            assert_max_recipients(deps, recipients.len())?;
+            assert_multi_asset_approved(deps, &recipients)?;
+            for recipient in recipients.iter() {
+                let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+                for (denom, amount) in aggregate_coins(recipient.coins.iter()) {
+                    assert_lifetime_cap(deps, &recipient_addr, &denom, amount)?;
+                }
+            }
+            Ok(required
+                .into_iter()
+                .map(|(denom, amount)| Coin { denom, amount })
+                .collect())
+        }
+        _ => Err(ContractError::Generic(
+            "Only distributions with fixed recipient amounts can be validated".to_string(),
+        )),
+    }
+}
+
+/// ## Description
+/// Rejects a native distribution whose total is below the configured minimum.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **total** is an object of type [`Uint128`] which is the total amount distributed.
+fn check_min_total(deps: Deps, total: Uint128) -> Result<(), ContractError> {
+    if let Some(min) = CONFIG.load(deps.storage)?.min_total {
+        if total < min {
+            return Err(ContractError::TotalBelowMinimum { min, got: total });
+        }
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Runs the recipient checks of the native and CW20 distributions and returns the total amount
+/// of the recipients that would be paid.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **asset** is a reference to an object of type [`str`] which is the native denom or CW20 contract address to distribute.
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount to check.
+///
+/// - **failure_policy** is a reference to an object of type [`FailurePolicy`] which decides how invalid recipient addresses are handled.
+///
+/// - **deferrable** is an object of type [`bool`] which is whether recipients that did not opt in can be deferred instead of rejected.
+fn check_batch_recipients(
+    deps: Deps,
+    asset: &str,
+    recipients: Vec<Recipient>,
+    failure_policy: &FailurePolicy,
+    deferrable: bool,
+) -> Result<Uint128, ContractError> {
+    validate_recipient_amounts(&recipients)?;
+    let recipients = filter_recipients(deps, recipients, failure_policy)?.kept;
+    if !deferrable {
+        assert_opted_in(deps, &recipients)?;
+    }
+    if has_duplicate_address(
+        recipients
+            .iter()
+            .map(|recipient| recipient.recipient.as_str()),
+    ) {
+        return Err(ContractError::DuplicateRecipient {});
+    }
+    assert_max_recipients(deps, recipients.len())?;
+    assert_approved(deps, asset, &recipients)?;
+    for recipient in recipients.iter() {
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+        assert_lifetime_cap(deps, &recipient_addr, asset, recipient.amount)?;
+    }
+    let total = recipients
+        .iter()
+        .try_fold(Uint128::zero(), |sum, recipient| {
+            sum.checked_add(recipient.amount)
+        })
+        .map_err(StdError::from)?;
+    Ok(total)
+}
+
+/// ## Description
+/// Returns the contract name and version recorded by cw2 along with the current configuration.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let version = get_contract_version(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(ConfigResponse {
+        contract_name: version.contract,
+        version: version.version,
+        owner: config.owner.map(|addr| addr.to_string()),
+        post_hook_contract: config.post_hook_contract.map(|addr| addr.to_string()),
+        max_denoms_per_tx: config.max_denoms_per_tx,
+        min_total: config.min_total,
+        policy_contract: config.policy_contract.map(|addr| addr.to_string()),
+        deny_policy: config.deny_policy,
+        treasury_fallback: config.treasury_fallback.map(|addr| addr.to_string()),
+        max_recipients: config.max_recipients,
+        bridge_registry: config.bridge_registry.map(|addr| addr.to_string()),
+        event_verbosity: config.event_verbosity,
+        address_format: config.address_format,
+        require_opt_in: config.require_opt_in,
+        approval_hook: config.approval_hook.map(|addr| addr.to_string()),
+        lifetime_cap: config.lifetime_cap,
+        vault_contract: config.vault_contract.map(|addr| addr.to_string()),
+        currencies: config.currencies,
+        unwrap_adapter: config.unwrap_adapter.map(|addr| addr.to_string()),
+        fallback_recipient: config.fallback_recipient.map(|addr| addr.to_string()),
+    })
+}
+
+/// ## Description
+/// Returns the amounts the recipients would receive if `target_total` were split by weight,
+/// using the same normalization and dust handling as the weighted distributions.
+///
+/// ## Params
+/// - **_deps** is an object of type [`Deps`].
+///
+/// - **recipients** is an object of type [`Vec<WeightedRecipient>`] which is the list of recipient address and weight.
+///
+/// - **target_total** is an object of type [`Uint128`] which is the total amount to split.
+///
+/// - **precision** is an [`Option`] of type [`Uint128`] which is the multiple each amount is rounded down to.
+pub fn query_normalize_weights(
+    _deps: Deps,
+    recipients: Vec<WeightedRecipient>,
+    target_total: Uint128,
+    precision: Option<Uint128>,
+) -> StdResult<NormalizeWeightsResponse> {
+    let (recipients, _) = split_with_minimums(target_total, recipients, precision)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    Ok(NormalizeWeightsResponse { recipients })
+}
+
+/// ## Description
+/// Returns the total amount an address has received across all distributions, either for a
+/// single asset or for every asset it has received.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **address** is an object of type [`String`] which is the recipient address.
+///
+/// - **asset** is an [`Option`] of type [`String`] which is the native denom or CW20 contract address to filter by.
+pub fn query_recipient_total(
+    deps: Deps,
+    address: String,
+    asset: Option<String>,
+) -> StdResult<RecipientTotalResponse> {
+    let recipient_addr = deps.api.addr_validate(&address)?;
+    let totals = match asset {
+        Some(asset) => {
+            let amount = RECIPIENT_TOTALS
+                .may_load(deps.storage, (&recipient_addr, &asset))?
+                .unwrap_or_default();
+            vec![AssetAmount { asset, amount }]
+        }
+        None => RECIPIENT_TOTALS
+            .prefix(&recipient_addr)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(asset, amount)| AssetAmount { asset, amount }))
+            .collect::<StdResult<Vec<_>>>()?,
+    };
+
+    Ok(RecipientTotalResponse { address, totals })
+}
+
+/// ## Description
+/// Returns the native balances deferred to a recipient.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipient** is an object of type [`String`] which is the recipient address.
+pub fn query_claimable_balance(
+    deps: Deps,
+    recipient: String,
+) -> StdResult<ClaimableBalanceResponse> {
+    let recipient_addr = deps.api.addr_validate(&recipient)?;
+    let amount = CLAIMABLE
+        .prefix(&recipient_addr)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ClaimableBalanceResponse { recipient, amount })
+}
+
+/// ## Description
+/// Compares the sum of the recipient amounts against the contract's free balance of `asset`.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **asset** is an object of type [`String`] which is the native denom to check.
+///
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipient address and amount.
+pub fn query_shortfall(
+    deps: Deps,
+    env: Env,
+    asset: String,
+    recipients: Vec<Recipient>,
+) -> StdResult<ShortfallResponse> {
+    let required = recipients
+        .iter()
+        .try_fold(Uint128::zero(), |sum, recipient| {
+            sum.checked_add(recipient.amount)
+        })
+        .map_err(StdError::overflow)?;
+    let available = free_balance(deps, &env, &asset)?;
+
+    Ok(ShortfallResponse {
+        required,
+        available,
+        shortfall: required.saturating_sub(available),
+    })
+}
+
+/// ## Description
+/// Returns every config parameter and its current JSON-serialized value.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+pub fn query_all_params(deps: Deps) -> StdResult<AllParamsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut params: BTreeMap<String, String> = BTreeMap::new();
+    let mut insert = |name: &str, value: Binary| -> StdResult<()> {
+        let value = String::from_utf8(value.0).map_err(StdError::invalid_utf8)?;
+        params.insert(name.to_string(), value);
+        Ok(())
+    };
+    insert("owner", to_binary(&config.owner)?)?;
+    insert("post_hook_contract", to_binary(&config.post_hook_contract)?)?;
+    insert("max_denoms_per_tx", to_binary(&config.max_denoms_per_tx)?)?;
+    insert("min_total", to_binary(&config.min_total)?)?;
+    insert("policy_contract", to_binary(&config.policy_contract)?)?;
+    insert("deny_policy", to_binary(&config.deny_policy)?)?;
+    insert("treasury_fallback", to_binary(&config.treasury_fallback)?)?;
+    insert("max_recipients", to_binary(&config.max_recipients)?)?;
+    insert("bridge_registry", to_binary(&config.bridge_registry)?)?;
+    insert("event_verbosity", to_binary(&config.event_verbosity)?)?;
+    insert("address_format", to_binary(&config.address_format)?)?;
+    insert("require_opt_in", to_binary(&config.require_opt_in)?)?;
+    insert("approval_hook", to_binary(&config.approval_hook)?)?;
+    insert("lifetime_cap", to_binary(&config.lifetime_cap)?)?;
+    insert("vault_contract", to_binary(&config.vault_contract)?)?;
+    insert("currencies", to_binary(&config.currencies)?)?;
+    insert("unwrap_adapter", to_binary(&config.unwrap_adapter)?)?;
+    insert("fallback_recipient", to_binary(&config.fallback_recipient)?)?;
+
+    Ok(AllParamsResponse { params })
+}
+
+/// ## Description
+/// Returns the native amounts held by the contract on behalf of deferred and escrowed recipients
+/// and senders of failed best-effort transfers, read from the reserved counters. These can be
+/// compared against the contract balance as a solvency check.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **denom** is an [`Option`] of type [`String`] which is the native denom to filter by.
+pub fn query_total_escrowed(deps: Deps, denom: Option<String>) -> StdResult<TotalEscrowedResponse> {
+    let escrowed = match denom {
+        Some(denom) => {
+            let amount = RESERVED.may_load(deps.storage, &denom)?.unwrap_or_default();
+            vec![Coin { denom, amount }]
+        }
+        None => RESERVED
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+            .collect::<StdResult<Vec<_>>>()?,
+    };
+
+    Ok(TotalEscrowedResponse { escrowed })
+}
+
+/// ## Description
+/// Decodes a CW20 hook message exactly as [`receive_cw20`] does, returning either the decoded
+/// message or the parse error.
+///
+/// ## Params
+/// - **_deps** is an object of type [`Deps`].
+///
+/// - **msg** is an object of type [`Binary`] which is the raw hook message.
+pub fn query_decode_hook(_deps: Deps, msg: Binary) -> StdResult<DecodeHookResponse> {
+    Ok(match from_binary::<Cw20HookMsg>(&msg) {
+        Ok(msg) => DecodeHookResponse {
+            msg: Some(msg),
+            error: None,
+        },
+        Err(err) => DecodeHookResponse {
+            msg: None,
+            error: Some(err.to_string()),
+        },
+    })
+}
+
+/// ## Description
+/// Estimates the bytes a distribution would persist. Every recipient adds a recipient total
+/// entry, and an escrow entry if the distribution is escrowed. Entries that already exist are
+/// counted too, so this is an upper bound.
+///
+/// ## Params
+/// - **_deps** is an object of type [`Deps`].
+///
+/// - **recipients** is an object of type [`u32`] which is the number of recipients.
+///
+/// - **escrowed** is an object of type [`bool`] which is whether every amount is escrowed.
+pub fn query_storage_estimate(
+    _deps: Deps,
+    recipients: u32,
+    escrowed: bool,
+) -> StdResult<StorageEstimateResponse> {
+    let per_recipient = if escrowed {
+        RECIPIENT_TOTAL_ENTRY_BYTES + ESCROW_ENTRY_BYTES
+    } else {
+        RECIPIENT_TOTAL_ENTRY_BYTES
+    };
+
+    Ok(StorageEstimateResponse {
+        bytes: recipients as u64 * per_recipient,
+    })
+}
+
+/// ## Description
+/// Suggests the number of recipients per call that keeps a native distribution under
+/// `gas_target`, estimating the gas of every recipient as [`GAS_PER_RECIPIENT`]. The page size
+/// never exceeds the configured maximum number of recipients per distribution.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipient_count** is an object of type [`u32`] which is the total number of recipients.
+///
+/// - **gas_target** is an object of type [`u64`] which is the gas each call should stay under.
+pub fn query_suggest_page_size(
+    deps: Deps,
+    recipient_count: u32,
+    gas_target: u64,
+) -> StdResult<SuggestPageSizeResponse> {
+    let by_gas = gas_target / GAS_PER_RECIPIENT;
+    if by_gas == 0 {
+        return Err(StdError::generic_err(format!(
+            "gas_target must be at least {}",
+            GAS_PER_RECIPIENT
+        )));
+    }
+    let mut page_size = by_gas.min(u32::MAX as u64) as u32;
+    if let Some(max) = CONFIG.load(deps.storage)?.max_recipients {
+        page_size = page_size.min(max.max(1));
+    }
+
+    Ok(SuggestPageSizeResponse {
+        page_size,
+        pages: recipient_count.div_ceil(page_size),
+    })
+}
+
+/// ## Description
+/// Returns whether the contract's free balance of `asset`, its balance minus the amounts reserved
+/// for deferred and escrowed recipients and failed best-effort transfers, covers `amount`.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **asset** is an object of type [`String`] which is the native denom to check.
+///
+/// - **amount** is an object of type [`Uint128`] which is the amount to pay out.
+pub fn query_can_afford(
+    deps: Deps,
+    env: Env,
+    asset: String,
+    amount: Uint128,
+) -> StdResult<CanAffordResponse> {
+    let available = free_balance(deps, &env, &asset)?;
+
+    Ok(CanAffordResponse {
+        can_afford: available >= amount,
+        available,
+    })
+}
+
+/// ## Description
+/// Buckets the escrowed amounts of `denom` that become claimable within `horizon_seconds` of the
+/// current block time into `buckets` equal intervals. Escrows that are already claimable but not
+/// yet claimed are counted in the first bucket; escrows released after the horizon are left out.
+/// The horizon and the number of buckets are bounded by [`MAX_FORECAST_HORIZON`] and
+/// [`MAX_FORECAST_BUCKETS`].
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **denom** is an object of type [`String`] which is the native denom to forecast.
+///
+/// - **horizon_seconds** is an object of type [`u64`] which is the length of the forecast in seconds.
+///
+/// - **buckets** is an object of type [`u32`] which is the number of buckets.
+pub fn query_release_forecast(
+    deps: Deps,
+    env: Env,
+    denom: String,
+    horizon_seconds: u64,
+    buckets: u32,
+) -> StdResult<ReleaseForecastResponse> {
+    if buckets == 0 || horizon_seconds == 0 {
+        return Err(StdError::generic_err(
+            "horizon_seconds and buckets must be greater than zero",
+        ));
+    }
+    if buckets > MAX_FORECAST_BUCKETS || horizon_seconds > MAX_FORECAST_HORIZON {
+        return Err(StdError::generic_err(format!(
+            "horizon_seconds must not exceed {} and buckets must not exceed {}",
+            MAX_FORECAST_HORIZON, MAX_FORECAST_BUCKETS
+        )));
+    }
+
+    let now = env.block.time;
+    let mut amounts = vec![Uint128::zero(); buckets as usize];
+    for item in ESCROWS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, escrow) = item?;
+        if escrow.denom != denom {
+            continue;
+        }
+        let offset = escrow.release_at.seconds().saturating_sub(now.seconds());
+        if offset >= horizon_seconds {
+            continue;
+        }
+        let index = (offset as u128 * buckets as u128 / horizon_seconds as u128) as usize;
+        amounts[index] += escrow.amount;
+    }
+
+    let buckets = amounts
+        .into_iter()
+        .enumerate()
+        .map(|(i, amount)| {
+            // bucket `i` holds the offsets `o` with `i * horizon <= o * buckets < (i + 1) * horizon`
+            let bound = |i: u128| (horizon_seconds as u128 * i).div_ceil(buckets as u128) as u64;
+            let start = bound(i as u128);
+            let end = bound(i as u128 + 1);
+            ReleaseBucket {
+                start: now.plus_seconds(start),
+                end: now.plus_seconds(end),
+                amount,
+            }
+        })
+        .collect();
+
+    Ok(ReleaseForecastResponse { buckets })
+}
+
+/// ## Description
+/// Returns whether an address has opted in to receiving distributions.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **address** is an object of type [`String`] which is the address to check.
+pub fn query_has_opted_in(deps: Deps, address: String) -> StdResult<HasOptedInResponse> {
+    let address = deps.api.addr_validate(&address)?;
+
+    Ok(HasOptedInResponse {
+        opted_in: has_opted_in(deps.storage, &address),
+    })
+}
+
+/// ## Description
+/// Returns whether an address is frozen from receiving distributions.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **address** is an object of type [`String`] which is the address to check.
+pub fn query_is_frozen(deps: Deps, address: String) -> StdResult<IsFrozenResponse> {
+    let address = deps.api.addr_validate(&address)?;
+
+    Ok(IsFrozenResponse {
+        frozen: is_frozen(deps.storage, &address),
+    })
+}
+
+/// ## Description
+/// Describes the execute messages from the JSON schema of [`ExecuteMsg`], so the description
+/// always matches the messages the contract accepts.
+pub fn query_message_schema() -> MessageSchemaResponse {
+    let schema = schema_for!(ExecuteMsg);
+    let variants = schema
+        .schema
+        .subschemas
+        .and_then(|subschemas| subschemas.one_of)
+        .unwrap_or_default();
+
+    // every variant is an object with a single property named after the message
+    let mut execute: Vec<MessageDescription> = vec![];
+    for variant in variants {
+        let object = match variant {
+            Schema::Object(SchemaObject {
+                object: Some(object),
+                ..
+            }) => object,
+            _ => continue,
+        };
+        for (name, body) in object.properties {
+            let required = match body {
+                Schema::Object(SchemaObject {
+                    object: Some(body), ..
+                }) => body.required.into_iter().collect(),
+                _ => vec![],
+            };
+            execute.push(MessageDescription { name, required });
+        }
+    }
+
+    MessageSchemaResponse { execute }
+}
+
+/// ## Description
+/// Compares two recipient lists by address. Amounts of an address listed more than once are
+/// summed. Added and changed recipients follow the order of `new`, removed recipients the order
+/// of `old`.
+///
+/// ## Params
+/// - **old** is an object of type [`Vec<Recipient>`] which is the previous list of recipients.
+///
+/// - **new** is an object of type [`Vec<Recipient>`] which is the updated list of recipients.
+pub fn query_diff_recipients(old: Vec<Recipient>, new: Vec<Recipient>) -> DiffRecipientsResponse {
+    let old = merge_recipient_amounts(old);
+    let new = merge_recipient_amounts(new);
+    let old_amounts: HashMap<&str, Uint128> = old
+        .iter()
+        .map(|(recipient, amount)| (recipient.as_str(), *amount))
+        .collect();
+    let new_amounts: HashMap<&str, Uint128> = new
+        .iter()
+        .map(|(recipient, amount)| (recipient.as_str(), *amount))
+        .collect();
+
+    let mut added: Vec<Recipient> = vec![];
+    let mut changed: Vec<RecipientChange> = vec![];
+    for (recipient, new_amount) in new.iter() {
+        match old_amounts.get(recipient.as_str()) {
+            None => added.push(Recipient {
+                recipient: recipient.clone(),
+                amount: *new_amount,
+                deferred: false,
+            }),
+            Some(old_amount) if old_amount != new_amount => changed.push(RecipientChange {
+                recipient: recipient.clone(),
+                old_amount: *old_amount,
+                new_amount: *new_amount,
+            }),
+            Some(_) => {}
+        }
+    }
+    let removed: Vec<Recipient> = old
+        .iter()
+        .filter(|(recipient, _)| !new_amounts.contains_key(recipient.as_str()))
+        .map(|(recipient, amount)| Recipient {
+            recipient: recipient.clone(),
+            amount: *amount,
+            deferred: false,
+        })
+        .collect();
+
+    DiffRecipientsResponse {
+        added,
+        removed,
+        changed,
+    }
+}
+
+/// ## Description
+/// Sums the amounts of every address in order of first appearance.
+///
+/// ## Params
+/// - **recipients** is an object of type [`Vec<Recipient>`] which is the list of recipients to merge.
+fn merge_recipient_amounts(recipients: Vec<Recipient>) -> Vec<(String, Uint128)> {
+    let mut merged: Vec<(String, Uint128)> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for recipient in recipients {
+        match index.get(&recipient.recipient) {
+            Some(i) => merged[*i].1 += recipient.amount,
+            None => {
+                index.insert(recipient.recipient.clone(), merged.len());
+                merged.push((recipient.recipient, recipient.amount));
+            }
+        }
+    }
+    merged
+}
+
+/// ## Description
+/// Applies `operations` in order against the contract's free balance and the recipients'
+/// allotted totals. An operation that fails leaves the simulated state untouched, so later
+/// operations are checked as if it was never sent.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **operations** is an object of type [`Vec<SimOp>`] which is the list of distributions to simulate.
+pub fn query_simulate_batch(
+    deps: Deps,
+    env: Env,
+    operations: Vec<SimOp>,
+) -> StdResult<SimulateBatchResponse> {
+    let mut balances: BTreeMap<String, Uint128> = BTreeMap::new();
+    let mut totals: HashMap<(Addr, String), Uint128> = HashMap::new();
+    let mut results: Vec<SimOpResult> = vec![];
+    for op in operations.iter() {
+        if !balances.contains_key(&op.denom) {
+            let available = free_balance(deps, &env, &op.denom)?;
+            balances.insert(op.denom.clone(), available);
+        }
+        let error = simulate_operation(deps, op, &mut balances, &mut totals)
+            .err()
+            .map(|err| err.to_string());
+        results.push(SimOpResult { error });
+    }
+
+    Ok(SimulateBatchResponse {
+        results,
+        remaining: balances
+            .into_iter()
+            .map(|(denom, amount)| Coin { denom, amount })
+            .collect(),
+    })
+}
+
+/// ## Description
+/// Checks one simulated distribution against the recipient checks of a native distribution,
+/// see [`check_batch_recipients`], and the simulated state, and applies it to that state if it
+/// would succeed.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **op** is a reference to an object of type [`SimOp`] which is the distribution to simulate.
+///
+/// - **balances** is a mutable reference to the simulated free balance per denom.
+///
+/// - **totals** is a mutable reference to the simulated amounts received per recipient and denom.
+fn simulate_operation(
+    deps: Deps,
+    op: &SimOp,
+    balances: &mut BTreeMap<String, Uint128>,
+    totals: &mut HashMap<(Addr, String), Uint128>,
+) -> Result<(), ContractError> {
+    let total = check_batch_recipients(
+        deps,
+        &op.denom,
+        op.recipients.clone(),
+        &FailurePolicy::AbortAll,
+        true,
+    )?;
+    check_min_total(deps, total)?;
+    let available = balances.get(&op.denom).copied().unwrap_or_default();
+    if total > available {
+        return Err(ContractError::Generic(format!(
+            "Insufficient free balance (available: {}, needed: {})",
+            available, total
+        )));
+    }
+
+    let lifetime_cap = CONFIG.load(deps.storage)?.lifetime_cap;
+    let mut received: Vec<((Addr, String), Uint128)> = vec![];
+    for recipient in op.recipients.iter() {
+        let recipient_addr = deps.api.addr_validate(&recipient.recipient)?;
+        let key = (recipient_addr, op.denom.clone());
+        let current = match totals.get(&key) {
+            Some(current) => *current,
+            None => allotted_total(deps.storage, &key.0, &op.denom)?,
+        };
+        let after = current
+            .checked_add(recipient.amount)
+            .map_err(StdError::from)?;
+        if let Some(cap) = lifetime_cap {
+            if after > cap {
+                return Err(ContractError::LifetimeCapExceeded {
+                    recipient: recipient.recipient.clone(),
+                    cap,
+                });
+            }
+        }
+        received.push((key, after));
+    }
+
+    totals.extend(received);
+    balances.insert(op.denom.clone(), available - total);
+    Ok(())
+}
+
+/// ## Description
+/// Exposes the migrate functionality in the contract.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **_env** is an object of type [`Env`].
+///
+/// - **msg** is an object of type [`MigrateMsg`] which optionally sets the owner.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    // contracts instantiated before the config existed start with the instantiate defaults
+    let mut config = CONFIG.may_load(deps.storage)?.unwrap_or_else(|| Config {
+        max_recipients: default_max_recipients(),
+        ..Config::default()
+    });
+    if let Some(owner) = msg.owner {
+        config.owner = Some(deps.api.addr_validate(&owner)?);
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+        MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{
+        Coin, ContractResult, OwnedDeps, ReplyOn, SubMsgResponse, SubMsgResult, SystemResult,
+        WasmQuery,
+    };
+
+    use crate::msg::{CurrencyRate, Cw4Member};
+
+    const OWNER: &str = "owner";
+    const DENOM: &str = "uluna";
+    const TOKEN: &str = "token";
+
+    fn instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            owner: OWNER.to_string(),
+            post_hook_contract: None,
+            max_denoms_per_tx: None,
+            min_total: None,
+            policy_contract: None,
+            deny_policy: DenyPolicy::Reject,
+            treasury_fallback: None,
+            max_recipients: Some(100),
+            bridge_registry: None,
+            event_verbosity: EventLevel::Full,
+            address_format: AddressFormat::Any,
+            require_opt_in: false,
+            approval_hook: None,
+            lifetime_cap: None,
+            vault_contract: None,
+            currencies: vec![],
+            unwrap_adapter: None,
+            fallback_recipient: None,
+        }
+    }
+
+    fn setup(msg: InstantiateMsg) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        deps
+    }
+
+    fn recipient(address: &str, amount: u128) -> Recipient {
+        Recipient {
+            recipient: address.to_string(),
+            amount: Uint128::new(amount),
+            deferred: false,
+        }
+    }
+
+    fn distribute_native_msg(recipients: Vec<Recipient>) -> ExecuteMsg {
+        ExecuteMsg::DistributeNative {
+            denom: DENOM.to_string(),
+            recipients,
+            failure_policy: FailurePolicy::AbortAll,
+            best_effort: false,
+            gas_reserve_denom: None,
+            display_decimals: None,
+            nonce: None,
+        }
+    }
+
+    fn distribute_cw20_msg(sender: &str, recipients: Vec<Recipient>) -> ExecuteMsg {
+        let amount = recipients.iter().map(|recipient| recipient.amount).sum();
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount,
+            msg: to_binary(&Cw20HookMsg::DistributeCw20 {
+                asset_token: TOKEN.to_string(),
+                recipients,
+                failure_policy: FailurePolicy::SkipInvalid,
+                nonce: None,
+                output: OutputKind::SameToken,
+            })
+            .unwrap(),
+        })
+    }
+
+    fn bank_sends(response: &Response) -> Vec<(String, Vec<Coin>)> {
+        response
+            .messages
+            .iter()
+            .filter_map(|msg| match &msg.msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    Some((to_address.clone(), amount.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn attribute<'a>(response: &'a Response, key: &str) -> Option<&'a str> {
+        response
+            .attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| attribute.value.as_str())
+    }
+
+    fn best_effort_msg(recipients: Vec<Recipient>) -> ExecuteMsg {
+        ExecuteMsg::DistributeNative {
+            denom: DENOM.to_string(),
+            recipients,
+            failure_policy: FailurePolicy::AbortAll,
+            best_effort: true,
+            gas_reserve_denom: None,
+            display_decimals: None,
+            nonce: None,
+        }
+    }
+
+    fn failed_reply(id: u64) -> Reply {
+        Reply {
+            id,
+            result: SubMsgResult::Err("cannot send to blocked address".to_string()),
+        }
+    }
+
+    #[test]
+    fn failure_policy_aborts_or_skips_invalid_recipients() {
+        let mut deps = setup(instantiate_msg());
+        let native_msg = |failure_policy: FailurePolicy| ExecuteMsg::DistributeNative {
+            denom: DENOM.to_string(),
+            recipients: vec![recipient("alice", 100), recipient("x", 200)],
+            failure_policy,
+            best_effort: false,
+            gas_reserve_denom: None,
+            display_decimals: None,
+            nonce: None,
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            native_msg(FailurePolicy::AbortAll),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            native_msg(FailurePolicy::SkipInvalid),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![("alice".to_string(), coins(100, DENOM))]
+        );
+        assert_eq!(attribute(&response, "skipped"), Some("x"));
+
+        // nothing skipped, nothing reported
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            distribute_cw20_msg(OWNER, vec![recipient("alice", 100)]),
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(attribute(&response, "skipped"), None);
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: OWNER.to_string(),
+                amount: Uint128::new(100),
+                msg: to_binary(&Cw20HookMsg::DistributeCw20 {
+                    asset_token: TOKEN.to_string(),
+                    recipients: vec![recipient("alice", 100), recipient("x", 50)],
+                    failure_policy: FailurePolicy::SkipInvalid,
+                    nonce: None,
+                    output: OutputKind::SameToken,
+                })
+                .unwrap(),
+            }),
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "skipped"), Some("x"));
+    }
+
+    #[test]
+    fn linear_amounts_interpolate_from_top_to_bottom() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(60, DENOM)),
+            ExecuteMsg::DistributeLinear {
+                denom: DENOM.to_string(),
+                recipients: vec!["alice".to_string(), "bob".to_string(), "carol".to_string()],
+                top_amount: Uint128::new(30),
+                bottom_amount: Uint128::new(10),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(30, DENOM)),
+                ("bob".to_string(), coins(20, DENOM)),
+                ("carol".to_string(), coins(10, DENOM)),
+            ]
+        );
+    }
+
+    fn multi_recipient(address: &str, amount: u128) -> MultiRecipient {
+        MultiRecipient {
+            recipient: address.to_string(),
+            coins: vec![Coin::new(amount, DENOM), Coin::new(amount, "uusd")],
+        }
+    }
+
+    #[test]
+    fn multi_asset_sends_every_coin_of_every_recipient() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(300, DENOM), Coin::new(300, "uusd")]),
+            ExecuteMsg::DistributeMultiAsset {
+                recipients: vec![multi_recipient("alice", 100), multi_recipient("bob", 200)],
+                atomic: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                (
+                    "alice".to_string(),
+                    vec![Coin::new(100, DENOM), Coin::new(100, "uusd")]
+                ),
+                (
+                    "bob".to_string(),
+                    vec![Coin::new(200, DENOM), Coin::new(200, "uusd")]
+                ),
+            ]
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(300, DENOM), Coin::new(299, "uusd")]),
+            ExecuteMsg::DistributeMultiAsset {
+                recipients: vec![multi_recipient("alice", 100), multi_recipient("bob", 200)],
+                atomic: true,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MismatchedAssetAmount {}));
+    }
+
+    #[test]
+    fn time_weighted_rewards_reconcile_with_the_shares() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(1_000, DENOM)),
+            ExecuteMsg::DistributeTimeWeighted {
+                precision: None,
+                denom: DENOM.to_string(),
+                snapshots: vec![
+                    ("alice".to_string(), Uint128::new(1)),
+                    ("bob".to_string(), Uint128::new(1)),
+                    ("carol".to_string(), Uint128::new(1)),
+                ],
+                snapshot_height: None,
+            },
+        )
+        .unwrap();
+        let sends = bank_sends(&response);
+        assert_eq!(
+            sends,
+            vec![
+                ("alice".to_string(), coins(333, DENOM)),
+                ("bob".to_string(), coins(333, DENOM)),
+                ("carol".to_string(), coins(334, DENOM)),
+            ]
+        );
+        let total: Uint128 = sends.iter().map(|(_, amount)| amount[0].amount).sum();
+        assert_eq!(total, Uint128::new(1_000));
+
+        // weights whose total overflows are rejected
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(1_000, DENOM)),
+            ExecuteMsg::DistributeTimeWeighted {
+                precision: None,
+                denom: DENOM.to_string(),
+                snapshots: vec![
+                    ("alice".to_string(), Uint128::MAX),
+                    ("bob".to_string(), Uint128::new(1)),
+                ],
+                snapshot_height: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+    }
+
+    fn validate(deps: Deps, message: ExecuteMsg) -> ValidateBatchResponse {
+        from_binary(&query(deps, mock_env(), QueryMsg::ValidateBatch { message }).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn validate_batch_reports_required_funds_or_the_failing_guard() {
+        let deps = setup(instantiate_msg());
+        let native_msg = |recipients: Vec<Recipient>| ExecuteMsg::DistributeNative {
+            denom: DENOM.to_string(),
+            recipients,
+            failure_policy: FailurePolicy::AbortAll,
+            best_effort: false,
+            gas_reserve_denom: None,
+            display_decimals: None,
+            nonce: None,
+        };
+
+        let response = validate(
+            deps.as_ref(),
+            native_msg(vec![recipient("alice", 100), recipient("bob", 200)]),
+        );
+        assert_eq!(
+            response,
+            ValidateBatchResponse {
+                valid: true,
+                error: None,
+                required_funds: coins(300, DENOM),
+            }
+        );
+        let response = validate(
+            deps.as_ref(),
+            ExecuteMsg::DistributeMultiAsset {
+                recipients: vec![multi_recipient("alice", 100), multi_recipient("bob", 200)],
+                atomic: true,
+            },
+        );
+        assert_eq!(
+            response.required_funds,
+            vec![Coin::new(300, DENOM), Coin::new(300, "uusd")]
+        );
+
+        // invalid address
+        let response = validate(deps.as_ref(), native_msg(vec![recipient("x", 100)]));
+        assert!(!response.valid);
+        assert!(response.required_funds.is_empty());
+        // duplicate recipient
+        let response = validate(
+            deps.as_ref(),
+            native_msg(vec![recipient("alice", 100), recipient("alice", 100)]),
+        );
+        assert_eq!(
+            response.error,
+            Some(ContractError::DuplicateRecipient {}.to_string())
+        );
+        // CW20 amount not matching the recipients
+        let mut cw20_msg = distribute_cw20_msg(OWNER, vec![recipient("alice", 100)]);
+        if let ExecuteMsg::Receive(receive_msg) = &mut cw20_msg {
+            receive_msg.amount = Uint128::new(99);
+        }
+        let response = validate(deps.as_ref(), cw20_msg);
+        assert_eq!(
+            response.error,
+            Some(ContractError::MismatchedAssetAmount {}.to_string())
+        );
+        // amounts that depend on the funds sent
+        let response = validate(
+            deps.as_ref(),
+            ExecuteMsg::DistributeTimeWeighted {
+                precision: None,
+                denom: DENOM.to_string(),
+                snapshots: vec![("alice".to_string(), Uint128::new(1))],
+                snapshot_height: None,
+            },
+        );
+        assert!(!response.valid);
+    }
+
+    fn weighted(address: &str, weight: u128) -> WeightedRecipient {
+        WeightedRecipient {
+            recipient: address.to_string(),
+            weight: Uint128::new(weight),
+            min_accept: None,
+        }
+    }
+
+    #[test]
+    fn weights_are_normalized_to_the_target_total() {
+        let deps = setup(instantiate_msg());
+        let response: NormalizeWeightsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::NormalizeWeights {
+                    precision: None,
+                    recipients: vec![
+                        weighted("alice", 1),
+                        weighted("bob", 2),
+                        weighted("carol", 3),
+                    ],
+                    target_total: Uint128::new(600),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            response.recipients,
+            vec![
+                recipient("alice", 100),
+                recipient("bob", 200),
+                recipient("carol", 300),
+            ]
+        );
+    }
+
+    #[test]
+    fn preferred_denoms_are_paid_while_available() {
+        let mut deps = setup(instantiate_msg());
+        let preferred = |address: &str, amount: u128, denom: Option<&str>| PreferredRecipient {
+            recipient: address.to_string(),
+            amount: Uint128::new(amount),
+            preferred_denom: denom.map(|denom| denom.to_string()),
+        };
+
+        // carol prefers uusd too, but only 150 of it is left after bob
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(400, DENOM), Coin::new(300, "uusd")]),
+            ExecuteMsg::DistributePreferred {
+                default_denom: DENOM.to_string(),
+                recipients: vec![
+                    preferred("alice", 100, Some(DENOM)),
+                    preferred("bob", 150, Some("uusd")),
+                    preferred("carol", 200, Some("uusd")),
+                    preferred("dave", 50, None),
+                ],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                ("bob".to_string(), coins(150, "uusd")),
+                ("carol".to_string(), coins(200, DENOM)),
+                ("dave".to_string(), coins(50, DENOM)),
+                (
+                    OWNER.to_string(),
+                    vec![Coin::new(50, DENOM), Coin::new(150, "uusd")]
+                ),
+            ]
+        );
+
+        // the default denom cannot cover the fallback
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(100, DENOM), Coin::new(100, "uusd")]),
+            ExecuteMsg::DistributePreferred {
+                default_denom: DENOM.to_string(),
+                recipients: vec![
+                    preferred("alice", 100, Some("uusd")),
+                    preferred("bob", 150, Some("uusd")),
+                ],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFunds { denom } if denom == DENOM));
+    }
+
+    #[test]
+    fn recipient_total_grows_across_distributions() {
+        let mut deps = setup(instantiate_msg());
+        for _ in 0..2 {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(OWNER, &coins(100, DENOM)),
+                distribute_native_msg(vec![recipient("alice", 100)]),
+            )
+            .unwrap();
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            distribute_cw20_msg(OWNER, vec![recipient("alice", 50)]),
+        )
+        .unwrap();
+
+        let response = query_recipient_total(deps.as_ref(), "alice".to_string(), None).unwrap();
+        assert_eq!(
+            response.totals,
+            vec![
+                AssetAmount {
+                    asset: TOKEN.to_string(),
+                    amount: Uint128::new(50),
+                },
+                AssetAmount {
+                    asset: DENOM.to_string(),
+                    amount: Uint128::new(200),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn group_with_reserve_carves_out_the_reserve() {
+        let mut deps = setup(instantiate_msg());
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let members = match from_binary(msg).unwrap() {
+                    Cw4QueryMsg::ListMembers {
+                        start_after: None, ..
+                    } => vec![
+                        Cw4Member {
+                            addr: "alice".to_string(),
+                            weight: 1,
+                        },
+                        Cw4Member {
+                            addr: "bob".to_string(),
+                            weight: 3,
+                        },
+                        Cw4Member {
+                            addr: "carol".to_string(),
+                            weight: 0,
+                        },
+                    ],
+                    Cw4QueryMsg::ListMembers { .. } => vec![],
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&Cw4MemberListResponse { members }).unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+        let group_msg = |reserve_to: &str| ExecuteMsg::DistributeGroupWithReserve {
+            precision: None,
+            denom: DENOM.to_string(),
+            group_addr: "group".to_string(),
+            reserve: Uint128::new(200),
+            reserve_to: reserve_to.to_string(),
+        };
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(1_000, DENOM)),
+            group_msg("reserve"),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("reserve".to_string(), coins(200, DENOM)),
+                ("alice".to_string(), coins(200, DENOM)),
+                ("bob".to_string(), coins(600, DENOM)),
+            ]
+        );
+
+        // a member receiving the reserve gets it on top of their share
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(1_000, DENOM)),
+            group_msg("bob"),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(200, DENOM)),
+                ("bob".to_string(), coins(800, DENOM)),
+            ]
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            group_msg("reserve"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MismatchedAssetAmount {}));
+    }
+
+    #[test]
+    fn precision_rounds_payouts_and_sweeps_the_dust() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(10_500, DENOM)),
+            ExecuteMsg::DistributeTimeWeighted {
+                denom: DENOM.to_string(),
+                snapshots: vec![
+                    ("alice".to_string(), Uint128::new(1)),
+                    ("bob".to_string(), Uint128::new(1)),
+                    ("carol".to_string(), Uint128::new(1)),
+                ],
+                precision: Some(Uint128::new(1_000)),
+                snapshot_height: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(3_000, DENOM)),
+                ("bob".to_string(), coins(3_000, DENOM)),
+                ("carol".to_string(), coins(4_500, DENOM)),
+            ]
+        );
+    }
+
+    #[test]
+    fn transfers_reply_only_in_best_effort_mode() {
+        let mut deps = setup(instantiate_msg());
+        for (best_effort, reply_on) in [(false, ReplyOn::Never), (true, ReplyOn::Error)] {
+            let response = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(OWNER, &coins(300, DENOM)),
+                ExecuteMsg::DistributeNative {
+                    denom: DENOM.to_string(),
+                    recipients: vec![recipient("alice", 100), recipient("bob", 200)],
+                    failure_policy: FailurePolicy::AbortAll,
+                    best_effort,
+                    gas_reserve_denom: None,
+                    display_decimals: None,
+                    nonce: None,
+                },
+            )
+            .unwrap();
+            assert_eq!(response.messages.len(), 2);
+            assert!(response.messages.iter().all(|msg| msg.reply_on == reply_on));
+        }
+    }
+
+    #[test]
+    fn decode_hook_parses_valid_and_reports_malformed_messages() {
+        let deps = setup(instantiate_msg());
+        let hook_msg = Cw20HookMsg::DistributeCw20 {
+            asset_token: TOKEN.to_string(),
+            recipients: vec![recipient("alice", 100)],
+            failure_policy: FailurePolicy::AbortAll,
+            nonce: None,
+            output: OutputKind::SameToken,
+        };
+        let response = query_decode_hook(deps.as_ref(), to_binary(&hook_msg).unwrap()).unwrap();
+        assert_eq!(response.msg, Some(hook_msg));
+        assert_eq!(response.error, None);
+
+        let response =
+            query_decode_hook(deps.as_ref(), Binary::from(b"{\"unknown\":{}}".to_vec())).unwrap();
+        assert_eq!(response.msg, None);
+        assert!(response.error.is_some());
+    }
+
+    #[test]
+    fn wrapped_distribution_transfers_the_minted_tokens() {
+        let mut deps = setup(instantiate_msg());
+        let set_balance = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                           balance: u128| {
+            deps.querier.update_wasm(move |_| {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&BalanceResponse {
+                        balance: Uint128::new(balance),
+                    })
+                    .unwrap(),
+                ))
+            })
+        };
+        let wrapped_msg = ExecuteMsg::DistributeWrapped {
+            denom: DENOM.to_string(),
+            wrapper: "wrapper".to_string(),
+            recipients: vec![recipient("alice", 100), recipient("bob", 200)],
+        };
+        let wrap_reply = || Reply {
+            id: WRAP_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+
+        set_balance(&mut deps, 50);
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            wrapped_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(response.messages[0].id, WRAP_REPLY_ID);
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "wrapper".to_string(),
+                msg: to_binary(&WrapperExecuteMsg::Wrap {}).unwrap(),
+                funds: coins(300, DENOM),
+            })
+        );
+
+        set_balance(&mut deps, 350);
+        let response = reply(deps.as_mut(), mock_env(), wrap_reply()).unwrap();
+        assert_eq!(attribute(&response, "wrapped_amount"), Some("300"));
+        let transfers: Vec<CosmosMsg> = response.messages.into_iter().map(|msg| msg.msg).collect();
+        assert_eq!(
+            transfers[1],
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "wrapper".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "bob".to_string(),
+                    amount: Uint128::new(200),
+                })
+                .unwrap(),
+            })
+        );
+        assert!(PENDING_WRAP.may_load(&deps.storage).unwrap().is_none());
+
+        // a wrapper minting less than sent aborts the distribution
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            wrapped_msg,
+        )
+        .unwrap();
+        set_balance(&mut deps, 600);
+        let err = reply(deps.as_mut(), mock_env(), wrap_reply()).unwrap_err();
+        assert_eq!(err.to_string(), "Wrapper minted 250 tokens instead of 300");
+    }
+
+    #[test]
+    fn best_effort_isolates_a_failing_transfer() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(600, DENOM)),
+            best_effort_msg(vec![
+                recipient("alice", 100),
+                recipient("bob", 200),
+                recipient("carol", 300),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(bank_sends(&response).len(), 3);
+
+        let response = reply(
+            deps.as_mut(),
+            mock_env(),
+            failed_reply(response.messages[1].id),
+        )
+        .unwrap();
+        assert!(response.messages.is_empty());
+        assert_eq!(attribute(&response, "failed_recipient"), Some("bob"));
+        assert_eq!(attribute(&response, "failed_amount"), Some("200"));
+        for (address, total) in [("alice", 100u128), ("bob", 0), ("carol", 300)] {
+            assert_eq!(
+                query_recipient_total(deps.as_ref(), address.to_string(), Some(DENOM.to_string()))
+                    .unwrap()
+                    .totals[0]
+                    .amount,
+                Uint128::new(total)
+            );
+        }
+
+        // only the sender can reclaim the failed transfer, once
+        let reclaim_msg = || ExecuteMsg::ReclaimFailed {
+            denom: DENOM.to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            reclaim_msg(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToReclaim { .. }));
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            reclaim_msg(),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![(OWNER.to_string(), coins(200, DENOM))]
+        );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            reclaim_msg(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToReclaim { .. }));
+    }
+
+    #[test]
+    fn conditional_pays_true_conditions_and_refunds_the_rest() {
+        let mut deps = setup(instantiate_msg());
+        let conditional_msg = |conditions: Vec<(&str, bool)>| ExecuteMsg::DistributeConditional {
+            denom: DENOM.to_string(),
+            recipients: vec![recipient("alice", 100), recipient("bob", 200)],
+            conditions: conditions
+                .into_iter()
+                .map(|(address, condition)| (address.to_string(), condition))
+                .collect(),
+        };
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            conditional_msg(vec![("alice", true), ("bob", false)]),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                (OWNER.to_string(), coins(200, DENOM)),
+            ]
+        );
+        assert_eq!(attribute(&response, "refund"), Some("200"));
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            conditional_msg(vec![("alice", false), ("bob", false)]),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![(OWNER.to_string(), coins(300, DENOM))]
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            conditional_msg(vec![("alice", false), ("bob", true), ("alice", true)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateCondition { .. }));
+    }
+
+    #[test]
+    fn post_hook_follows_the_transfers() {
+        let mut deps = setup(InstantiateMsg {
+            post_hook_contract: Some("hook".to_string()),
+            ..instantiate_msg()
+        });
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100), recipient("bob", 200)]),
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 3);
+        assert_eq!(bank_sends(&response).len(), 2);
+        assert_eq!(
+            response.messages[2].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "hook".to_string(),
+                funds: vec![],
+                msg: to_binary(&PostHookMsg::DistributionSummary {
+                    sender: OWNER.to_string(),
+                    amount: coins(300, DENOM),
+                    num_recipients: 2,
+                })
+                .unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn deferred_recipients_claim_what_immediate_ones_are_sent() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            distribute_native_msg(vec![
+                recipient("alice", 100),
+                Recipient {
+                    deferred: true,
+                    ..recipient("bob", 200)
+                },
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![("alice".to_string(), coins(100, DENOM))]
+        );
+        assert_eq!(
+            query_claimable_balance(deps.as_ref(), "bob".to_string())
+                .unwrap()
+                .amount,
+            coins(200, DENOM)
+        );
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::ClaimDeferred {},
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![("bob".to_string(), coins(200, DENOM))]
+        );
+        assert!(query_claimable_balance(deps.as_ref(), "bob".to_string())
+            .unwrap()
+            .amount
+            .is_empty());
+    }
+
+    #[test]
+    fn shortfall_compares_recipients_against_the_free_balance() {
+        let mut deps = setup(instantiate_msg());
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(300, DENOM));
+        for &(required, shortfall) in &[(200u128, 0u128), (300, 0), (450, 150)] {
+            let response = query_shortfall(
+                deps.as_ref(),
+                mock_env(),
+                DENOM.to_string(),
+                vec![recipient("alice", 100), recipient("bob", required - 100)],
+            )
+            .unwrap();
+            assert_eq!(
+                response,
+                ShortfallResponse {
+                    required: Uint128::new(required),
+                    available: Uint128::new(300),
+                    shortfall: Uint128::new(shortfall),
+                }
+            );
+        }
+
+        let err = query_shortfall(
+            deps.as_ref(),
+            mock_env(),
+            DENOM.to_string(),
+            vec![recipient("alice", u128::MAX), recipient("bob", 1)],
+        )
+        .unwrap_err();
+        assert!(matches!(err, StdError::Overflow { .. }));
+
+        // a failed best-effort transfer stays reserved for its sender until reclaimed
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            best_effort_msg(vec![recipient("alice", 100)]),
+        )
+        .unwrap();
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            failed_reply(response.messages[0].id),
+        )
+        .unwrap();
+        let available = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            query_shortfall(deps.as_ref(), mock_env(), DENOM.to_string(), vec![])
+                .unwrap()
+                .available
+        };
+        assert_eq!(available(&deps), Uint128::new(200));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::ReclaimFailed {
+                denom: DENOM.to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(available(&deps), Uint128::new(300));
+    }
+
+    #[test]
+    fn gas_reserve_is_refunded_to_the_sender() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(300, DENOM), Coin::new(50, "uusd")]),
+            ExecuteMsg::DistributeNative {
+                denom: DENOM.to_string(),
+                recipients: vec![recipient("alice", 100), recipient("bob", 200)],
+                failure_policy: FailurePolicy::AbortAll,
+                best_effort: false,
+                gas_reserve_denom: Some("uusd".to_string()),
+                display_decimals: None,
+                nonce: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                ("bob".to_string(), coins(200, DENOM)),
+                (OWNER.to_string(), coins(50, "uusd")),
+            ]
+        );
+    }
+
+    #[test]
+    fn all_params_lists_every_config_parameter() {
+        let deps = setup(InstantiateMsg {
+            post_hook_contract: Some("hook".to_string()),
+            ..instantiate_msg()
+        });
+        let params = query_all_params(deps.as_ref()).unwrap().params;
+        let keys: Vec<&str> = params.keys().map(|key| key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "address_format",
+                "approval_hook",
+                "bridge_registry",
+                "currencies",
+                "deny_policy",
+                "event_verbosity",
+                "fallback_recipient",
+                "lifetime_cap",
+                "max_denoms_per_tx",
+                "max_recipients",
+                "min_total",
+                "owner",
+                "policy_contract",
+                "post_hook_contract",
+                "require_opt_in",
+                "treasury_fallback",
+                "unwrap_adapter",
+                "vault_contract"
+            ]
+        );
+        assert_eq!(params["post_hook_contract"], "\"hook\"");
+    }
+
+    #[test]
+    fn snapshot_height_is_recorded_in_the_attributes() {
+        let mut deps = setup(instantiate_msg());
+        let time_weighted_msg = |snapshot_height: Option<u64>| ExecuteMsg::DistributeTimeWeighted {
+            denom: DENOM.to_string(),
+            snapshots: vec![("alice".to_string(), Uint128::new(1))],
+            precision: None,
+            snapshot_height,
+        };
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            time_weighted_msg(Some(12_345)),
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "snapshot_height"), Some("12345"));
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            time_weighted_msg(None),
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "snapshot_height"), None);
+    }
+
+    #[test]
+    fn max_denoms_per_tx_bounds_multi_asset_distributions() {
+        let mut deps = setup(InstantiateMsg {
+            max_denoms_per_tx: Some(2),
+            ..instantiate_msg()
+        });
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(100, DENOM), Coin::new(100, "uusd")]),
+            ExecuteMsg::DistributeMultiAsset {
+                recipients: vec![multi_recipient("alice", 100)],
+                atomic: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(bank_sends(&response).len(), 1);
+
+        let coins_sent = vec![
+            Coin::new(100, DENOM),
+            Coin::new(100, "ukrw"),
+            Coin::new(100, "uusd"),
+        ];
+        let multi_asset_msg = ExecuteMsg::DistributeMultiAsset {
+            recipients: vec![MultiRecipient {
+                recipient: "alice".to_string(),
+                coins: coins_sent.clone(),
+            }],
+            atomic: true,
+        };
+        assert_eq!(
+            validate(deps.as_ref(), multi_asset_msg.clone()).error,
+            Some(ContractError::TooManyDenoms { limit: 2, got: 3 }.to_string())
+        );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins_sent),
+            multi_asset_msg,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::TooManyDenoms { limit: 2, got: 3 }
+        ));
+    }
+
+    #[test]
+    fn repayments_are_capped_by_the_debts() {
+        let mut deps = setup(instantiate_msg());
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let amount = match from_binary(msg).unwrap() {
+                    LendingQueryMsg::Debt { borrower } if borrower == "alice" => 30u128,
+                    LendingQueryMsg::Debt { .. } => 500,
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&DebtResponse {
+                        amount: Uint128::new(amount),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(200, DENOM)),
+            ExecuteMsg::RepayDebts {
+                denom: DENOM.to_string(),
+                lending_addr: "lending".to_string(),
+                borrowers: vec!["alice".to_string(), "bob".to_string()],
+            },
+        )
+        .unwrap();
+        let repay_msg = |borrower: &str, amount: u128| {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "lending".to_string(),
+                funds: coins(amount, DENOM),
+                msg: to_binary(&LendingExecuteMsg::RepayOnBehalf {
+                    borrower: borrower.to_string(),
+                })
+                .unwrap(),
+            })
+        };
+        assert_eq!(
+            response
+                .messages
+                .iter()
+                .map(|msg| msg.msg.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                repay_msg("alice", 30),
+                repay_msg("bob", 100),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: OWNER.to_string(),
+                    amount: coins(70, DENOM),
+                }),
+            ]
+        );
+        assert_eq!(attribute(&response, "refund"), Some("70"));
+    }
+
+    #[test]
+    fn total_escrowed_sums_deferred_and_failed_amounts() {
+        let mut deps = setup(instantiate_msg());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            distribute_native_msg(vec![Recipient {
+                deferred: true,
+                ..recipient("alice", 100)
+            }]),
+        )
+        .unwrap();
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(50, DENOM)),
+            best_effort_msg(vec![recipient("bob", 50)]),
+        )
+        .unwrap();
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            failed_reply(response.messages[0].id),
+        )
+        .unwrap();
+
+        assert_eq!(
+            query_total_escrowed(deps.as_ref(), Some(DENOM.to_string()))
+                .unwrap()
+                .escrowed,
+            coins(150, DENOM)
+        );
+        assert_eq!(
+            query_total_escrowed(deps.as_ref(), None).unwrap().escrowed,
+            coins(150, DENOM)
+        );
+    }
+
+    #[test]
+    fn matched_recipients_receive_base_plus_match() {
+        let mut deps = setup(instantiate_msg());
+        let matched_msg = || ExecuteMsg::DistributeMatched {
+            denom: DENOM.to_string(),
+            recipients: vec![recipient("alice", 100), recipient("bob", 205)],
+            sponsor: "sponsor".to_string(),
+            match_bps: 5_000,
+        };
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(457, DENOM)),
+            matched_msg(),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(150, DENOM)),
+                ("bob".to_string(), coins(307, DENOM)),
+            ]
+        );
+        assert_eq!(attribute(&response, "sponsor"), Some("sponsor"));
+        assert_eq!(attribute(&response, "matched_amount"), Some("152"));
+
+        // the base amounts alone do not cover the match
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(305, DENOM)),
+            matched_msg(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MismatchedAssetAmount {}));
+    }
+
+    #[test]
+    fn display_amounts_and_post_hook_cover_the_sent_recipients() {
+        let mut deps = setup(InstantiateMsg {
+            post_hook_contract: Some("hook".to_string()),
+            ..instantiate_msg()
+        });
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(1_734_567, DENOM)),
+            ExecuteMsg::DistributeNative {
+                denom: DENOM.to_string(),
+                recipients: vec![
+                    recipient("alice", 1_234_567),
+                    Recipient {
+                        deferred: true,
+                        ..recipient("bob", 500_000)
+                    },
+                ],
+                failure_policy: FailurePolicy::AbortAll,
+                best_effort: false,
+                gas_reserve_denom: None,
+                display_decimals: Some(6),
+                nonce: None,
+            },
+        )
+        .unwrap();
+        let display_events: Vec<&Event> = response
+            .events
+            .iter()
+            .filter(|event| event.ty == "display_amount")
+            .collect();
+        assert_eq!(
+            display_events,
+            vec![&Event::new("display_amount")
+                .add_attribute("recipient", "alice")
+                .add_attribute("amount", "1234567")
+                .add_attribute("display_amount", "1.234567")]
+        );
+        assert_eq!(
+            response.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "hook".to_string(),
+                funds: vec![],
+                msg: to_binary(&PostHookMsg::DistributionSummary {
+                    sender: OWNER.to_string(),
+                    amount: coins(1_234_567, DENOM),
+                    num_recipients: 1,
+                })
+                .unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn min_total_rejects_dust_sized_distributions() {
+        let mut deps = setup(InstantiateMsg {
+            min_total: Some(Uint128::new(100)),
+            ..instantiate_msg()
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100)]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            validate(
+                deps.as_ref(),
+                distribute_native_msg(vec![recipient("alice", 99)])
+            )
+            .error,
+            Some("Distribution total is below the minimum (min: 100, got: 99)".to_string())
+        );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(99, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 99)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::TotalBelowMinimum { .. }));
+    }
+
+    #[test]
+    fn duplicate_address_detected_anywhere() {
+        assert!(!has_duplicate_address(
+            vec!["alice", "bob", "carol"].into_iter()
+        ));
+        assert!(has_duplicate_address(
+            vec!["alice", "bob", "carol", "alice"].into_iter()
+        ));
+
+        let mut deps = setup(instantiate_msg());
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            distribute_native_msg(vec![
+                recipient("alice", 100),
+                recipient("bob", 50),
+                recipient("alice", 150),
+            ]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateRecipient {}));
+    }
+
+    #[test]
+    fn duplicate_address_detected_in_large_unsorted_list() {
+        let mut addresses: Vec<String> = (0..500)
+            .map(|i| format!("addr{}", (i * 7919) % 500))
+            .collect();
+        assert!(!has_duplicate_address(
+            addresses.iter().map(|address| address.as_str())
+        ));
+        addresses.push("addr250".to_string());
+        assert!(has_duplicate_address(
+            addresses.iter().map(|address| address.as_str())
+        ));
+    }
+
+    #[test]
+    fn equal_value_is_priced_by_the_oracle() {
+        let mut deps = setup(instantiate_msg());
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let OracleQueryMsg::Price { denom } = from_binary(msg).unwrap();
+                assert_eq!(denom, DENOM);
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&PriceResponse {
+                        price: Decimal::percent(250),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(301, DENOM)),
+            ExecuteMsg::DistributeEqualValue {
+                denom: DENOM.to_string(),
+                recipients: vec!["alice".to_string(), "bob".to_string(), "carol".to_string()],
+                oracle_addr: "oracle".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                ("bob".to_string(), coins(100, DENOM)),
+                ("carol".to_string(), coins(101, DENOM)),
+            ]
+        );
+        assert_eq!(attribute(&response, "price"), Some("2.5"));
+        assert_eq!(attribute(&response, "value_per_recipient"), Some("250"));
+    }
+
+    #[test]
+    fn sent_native_amount_requires_exactly_one_coin() {
+        let err = sent_native_amount(&mock_info(OWNER, &[]), DENOM).unwrap_err();
+        assert!(matches!(err, ContractError::NoFundsSent {}));
+
+        let amount = sent_native_amount(&mock_info(OWNER, &coins(100, DENOM)), DENOM).unwrap();
+        assert_eq!(amount, Uint128::new(100));
+
+        let funds = vec![Coin::new(100, DENOM), Coin::new(100, "uusd")];
+        let err = sent_native_amount(&mock_info(OWNER, &funds), DENOM).unwrap_err();
+        assert!(matches!(err, ContractError::MismatchedAssetType {}));
+        let funds = vec![Coin::new(50, DENOM), Coin::new(50, DENOM)];
+        let err = sent_native_amount(&mock_info(OWNER, &funds), DENOM).unwrap_err();
+        assert!(matches!(err, ContractError::MismatchedAssetType {}));
+    }
+
+    #[test]
+    fn config_query_returns_contract_version() {
+        let deps = setup(instantiate_msg());
+        let config: ConfigResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.contract_name, CONTRACT_NAME);
+        assert_eq!(config.version, CONTRACT_VERSION);
+        assert_eq!(config.owner, Some(OWNER.to_string()));
+        assert_eq!(config.post_hook_contract, None);
+    }
+
+    fn mock_policy(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, denied: &'static str) {
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let PolicyQueryMsg::Check { address } = from_binary(msg).unwrap();
+                let allowed = address != denied;
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&CheckResponse { allowed }).unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+    }
+
+    #[test]
+    fn policy_contract_allows_and_denies_native_recipients() {
+        for deny_policy in [DenyPolicy::Reject, DenyPolicy::Drop] {
+            let mut deps = setup(InstantiateMsg {
+                policy_contract: Some("policy".to_string()),
+                deny_policy: deny_policy.clone(),
+                ..instantiate_msg()
+            });
+            mock_policy(&mut deps, "mallory");
+            let result = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(OWNER, &coins(100, DENOM)),
+                distribute_native_msg(vec![recipient("alice", 100), recipient("mallory", 50)]),
+            );
+            match deny_policy {
+                DenyPolicy::Reject => assert!(matches!(
+                    result.unwrap_err(),
+                    ContractError::RecipientDenied { .. }
+                )),
+                DenyPolicy::Drop => {
+                    let response = result.unwrap();
+                    assert_eq!(
+                        bank_sends(&response),
+                        vec![("alice".to_string(), coins(100, DENOM))]
+                    );
+                    assert_eq!(attribute(&response, "skipped"), Some("mallory"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn multi_asset_recipients_are_screened() {
+        let mut deps = setup(InstantiateMsg {
+            policy_contract: Some("policy".to_string()),
+            deny_policy: DenyPolicy::Drop,
+            ..instantiate_msg()
+        });
+        mock_policy(&mut deps, "mallory");
+        let multi_asset_msg = ExecuteMsg::DistributeMultiAsset {
+            recipients: vec![
+                multi_recipient("alice", 100),
+                multi_recipient("mallory", 50),
+            ],
+            atomic: true,
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(100, DENOM), Coin::new(100, "uusd")]),
+            multi_asset_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![(
+                "alice".to_string(),
+                vec![Coin::new(100, DENOM), Coin::new(100, "uusd")]
+            )]
+        );
+        assert_eq!(attribute(&response, "skipped"), Some("mallory"));
+        let validated = query_validate_batch(deps.as_ref(), multi_asset_msg.clone()).unwrap();
+        assert_eq!(
+            validated.required_funds,
+            vec![Coin::new(100, DENOM), Coin::new(100, "uusd")]
+        );
+
+        let mut deps = setup(InstantiateMsg {
+            policy_contract: Some("policy".to_string()),
+            ..instantiate_msg()
+        });
+        mock_policy(&mut deps, "mallory");
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(150, DENOM), Coin::new(150, "uusd")]),
+            multi_asset_msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RecipientDenied { .. }));
+        let validated = query_validate_batch(deps.as_ref(), multi_asset_msg).unwrap();
+        assert!(!validated.valid);
+    }
+
+    #[test]
+    fn preferred_recipients_are_screened() {
+        let mut deps = setup(InstantiateMsg {
+            policy_contract: Some("policy".to_string()),
+            deny_policy: DenyPolicy::Drop,
+            ..instantiate_msg()
+        });
+        mock_policy(&mut deps, "mallory");
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(150, DENOM)),
+            ExecuteMsg::DistributePreferred {
+                default_denom: DENOM.to_string(),
+                recipients: vec![
+                    PreferredRecipient {
+                        recipient: "alice".to_string(),
+                        amount: Uint128::new(100),
+                        preferred_denom: None,
+                    },
+                    PreferredRecipient {
+                        recipient: "mallory".to_string(),
+                        amount: Uint128::new(50),
+                        preferred_denom: None,
+                    },
+                ],
+            },
+        )
+        .unwrap();
+        // mallory's share is refunded along with the leftovers
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                (OWNER.to_string(), coins(50, DENOM)),
+            ]
+        );
+        assert_eq!(attribute(&response, "skipped"), Some("mallory"));
+    }
+
+    #[test]
+    fn owner_distributes_native() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100), recipient("bob", 200)]),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                ("bob".to_string(), coins(200, DENOM)),
+            ]
+        );
+    }
+
+    #[test]
+    fn owner_distributes_cw20() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            distribute_cw20_msg(OWNER, vec![recipient("alice", 100)]),
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 1);
+    }
+
+    #[test]
+    fn non_owner_cannot_distribute() {
+        let mut deps = setup(instantiate_msg());
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mallory", &coins(100, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            distribute_cw20_msg("mallory", vec![recipient("alice", 100)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mallory", &[Coin::new(100, DENOM), Coin::new(100, "uusd")]),
+            ExecuteMsg::DistributeMultiAsset {
+                recipients: vec![multi_recipient("alice", 100)],
+                atomic: true,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mallory", &coins(100, DENOM)),
+            ExecuteMsg::DistributePreferred {
+                default_denom: DENOM.to_string(),
+                recipients: vec![PreferredRecipient {
+                    recipient: "alice".to_string(),
+                    amount: Uint128::new(100),
+                    preferred_denom: None,
+                }],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mallory", &coins(100, DENOM)),
+            ExecuteMsg::DistributeWrapped {
+                denom: DENOM.to_string(),
+                wrapper: "wrapper".to_string(),
+                recipients: vec![recipient("alice", 100)],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn update_owner_hands_off_control() {
+        let mut deps = setup(instantiate_msg());
+        let update = ExecuteMsg::UpdateOwner {
+            new_owner: "successor".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mallory", &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), update).unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("successor", &coins(100, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100)]),
+        )
+        .unwrap();
+    }
+    fn share(address: &str, weight: u64) -> Share {
+        Share {
+            recipient: address.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn shares_split_by_weight() {
+        let recipients = split_by_weight(
+            Uint128::new(1000),
+            share_weights(vec![
+                share("alice", 50),
+                share("bob", 30),
+                share("carol", 20),
+            ]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            recipients,
+            vec![
+                recipient("alice", 500),
+                recipient("bob", 300),
+                recipient("carol", 200),
+            ]
+        );
+
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            ExecuteMsg::DistributeNativeByShares {
+                denom: DENOM.to_string(),
+                shares: vec![share("alice", 1), share("bob", 1), share("carol", 1)],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(33, DENOM)),
+                ("bob".to_string(), coins(33, DENOM)),
+                ("carol".to_string(), coins(34, DENOM)),
+            ]
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            ExecuteMsg::DistributeNativeByShares {
+                denom: DENOM.to_string(),
+                shares: vec![share("alice", 0), share("bob", 0)],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ZeroTotalWeight {}));
+
+        // a cw20 share split validates against the amount carried in the message
+        let validated = query_validate_batch(
+            deps.as_ref(),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: OWNER.to_string(),
+                amount: Uint128::new(100),
+                msg: to_binary(&Cw20HookMsg::DistributeCw20ByShares {
+                    asset_token: TOKEN.to_string(),
+                    shares: vec![share("alice", 1), share("alice", 1)],
+                })
+                .unwrap(),
+            }),
+        )
+        .unwrap();
+        assert_eq!(
+            validated.error,
+            Some(ContractError::DuplicateRecipient {}.to_string())
+        );
+    }
+
+    #[test]
+    fn random_winners_are_reproducible_for_a_fixed_seed() {
+        let draw = |seed: &[u8]| {
+            let mut deps = setup(instantiate_msg());
+            let response = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(OWNER, &coins(100, DENOM)),
+                ExecuteMsg::DistributeRandom {
+                    denom: DENOM.to_string(),
+                    candidates: vec![
+                        "alice".to_string(),
+                        "bob".to_string(),
+                        "carol".to_string(),
+                        "dave".to_string(),
+                    ],
+                    winners: 2,
+                    seed: Binary::from(seed),
+                },
+            )
+            .unwrap();
+            assert_eq!(bank_sends(&response).len(), 2);
+            attribute(&response, "winners").unwrap().to_string()
+        };
+
+        let winners = draw(b"seed");
+        assert_eq!(winners, draw(b"seed"));
+        assert_eq!(winners.split(',').count(), 2);
+    }
+    #[test]
+    fn empty_and_zero_amount_recipients_rejected() {
+        let mut deps = setup(instantiate_msg());
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            distribute_native_msg(vec![]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::EmptyRecipients {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 0)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ZeroAmount {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            distribute_cw20_msg(
+                OWNER,
+                vec![
+                    recipient("alice", 100),
+                    recipient("bob", 0),
+                    recipient("carol", 50),
+                ],
+            ),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ZeroAmount {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            ExecuteMsg::DistributeWrapped {
+                denom: DENOM.to_string(),
+                wrapper: "wrapper".to_string(),
+                recipients: vec![recipient("alice", 100), recipient("bob", 0)],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ZeroAmount {}));
+
+        let validated = query_validate_batch(deps.as_ref(), distribute_native_msg(vec![])).unwrap();
+        assert_eq!(
+            validated.error,
+            Some(ContractError::EmptyRecipients {}.to_string())
+        );
+    }
+
+    #[test]
+    fn distribution_emits_attributes_and_events() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100), recipient("bob", 200)]),
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "action"), Some("distribute_native"));
+        assert_eq!(attribute(&response, "denom"), Some(DENOM));
+        assert_eq!(attribute(&response, "total_amount"), Some("300"));
+        assert_eq!(attribute(&response, "num_recipients"), Some("2"));
+        assert_eq!(
+            response.events,
+            vec![
+                Event::new("distribute_transfer")
+                    .add_attribute("recipient", "alice")
+                    .add_attribute("amount", "100"),
+                Event::new("distribute_transfer")
+                    .add_attribute("recipient", "bob")
+                    .add_attribute("amount", "200"),
+            ]
+        );
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            distribute_cw20_msg(OWNER, vec![recipient("alice", 100), recipient("bob", 200)]),
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "action"), Some("distribute_cw20"));
+        assert_eq!(attribute(&response, "asset_token"), Some(TOKEN));
+        assert_eq!(attribute(&response, "total_amount"), Some("300"));
+        assert_eq!(attribute(&response, "num_recipients"), Some("2"));
+        assert_eq!(response.events.len(), 2);
+    }
+
+    #[test]
+    fn undeliverable_funds_land_in_the_treasury_fallback() {
+        let mut deps = setup(InstantiateMsg {
+            treasury_fallback: Some("treasury".to_string()),
+            ..instantiate_msg()
+        });
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            best_effort_msg(vec![recipient("alice", 100), recipient("bob", 200)]),
+        )
+        .unwrap();
+
+        let response = reply(
+            deps.as_mut(),
+            mock_env(),
+            failed_reply(response.messages[1].id),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![("treasury".to_string(), coins(200, DENOM))]
+        );
+        assert_eq!(attribute(&response, "fallback_recipient"), Some("treasury"));
+
+        // funds sent on to the treasury are not reclaimable
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::ReclaimFailed {
+                denom: DENOM.to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToReclaim { .. }));
+        assert_eq!(
+            query_total_escrowed(deps.as_ref(), Some(DENOM.to_string()))
+                .unwrap()
+                .escrowed,
+            vec![Coin::new(0, DENOM)]
+        );
+    }
+
+    #[test]
+    fn max_recipients_bounds_distribution() {
+        let mut deps = setup(InstantiateMsg {
+            max_recipients: Some(2),
+            ..instantiate_msg()
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100), recipient("bob", 200)]),
+        )
+        .unwrap();
+
+        let too_many = distribute_native_msg(vec![
+            recipient("alice", 100),
+            recipient("bob", 200),
+            recipient("carol", 100),
+        ]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(400, DENOM)),
+            too_many.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::TooManyRecipients { max: 2, got: 3 }
+        ));
+        let validated = query_validate_batch(deps.as_ref(), too_many).unwrap();
+        assert_eq!(validated.error, Some(err.to_string()));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(300, DENOM), Coin::new(300, "uusd")]),
+            ExecuteMsg::DistributeMultiAsset {
+                recipients: vec![
+                    multi_recipient("alice", 100),
+                    multi_recipient("bob", 100),
+                    multi_recipient("carol", 100),
+                ],
+                atomic: true,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::TooManyRecipients { max: 2, got: 3 }
+        ));
+
+        let mut deps = setup(InstantiateMsg {
+            max_recipients: None,
+            ..instantiate_msg()
+        });
+        let recipients: Vec<Recipient> = (0..150)
+            .map(|i| recipient(&format!("recipient{}", i), 1))
+            .collect();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(150, DENOM)),
+            distribute_native_msg(recipients),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn migrate_backfills_default_max_recipients() {
+        let mut deps = mock_dependencies();
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                owner: Some(OWNER.to_string()),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().max_recipients,
+            Some(100)
+        );
+    }
+
+    #[test]
+    fn equal_split_sends_dust_to_last_recipient() {
+        let mut deps = setup(instantiate_msg());
+        let recipients = vec!["alice".to_string(), "bob".to_string(), "carol".to_string()];
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(300, DENOM)),
+            ExecuteMsg::DistributeNativeEqual {
+                denom: DENOM.to_string(),
+                recipients: recipients.clone(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                ("bob".to_string(), coins(100, DENOM)),
+                ("carol".to_string(), coins(100, DENOM)),
+            ]
+        );
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(302, DENOM)),
+            ExecuteMsg::DistributeNativeEqual {
+                denom: DENOM.to_string(),
+                recipients,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                ("bob".to_string(), coins(100, DENOM)),
+                ("carol".to_string(), coins(102, DENOM)),
+            ]
+        );
+    }
+
+    #[test]
+    fn cw20_equal_split_sums_to_the_received_amount() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: OWNER.to_string(),
+                amount: Uint128::new(302),
+                msg: to_binary(&Cw20HookMsg::DistributeCw20Equal {
+                    asset_token: TOKEN.to_string(),
+                    recipients: vec!["alice".to_string(), "bob".to_string(), "carol".to_string()],
+                })
+                .unwrap(),
+            }),
+        )
+        .unwrap();
+        let transfers: Vec<(String, Uint128)> = response
+            .messages
+            .iter()
+            .map(|msg| match &msg.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr, msg, ..
+                }) => {
+                    assert_eq!(contract_addr, TOKEN);
+                    match from_binary(msg).unwrap() {
+                        Cw20ExecuteMsg::Transfer { recipient, amount } => (recipient, amount),
+                        _ => panic!("unexpected cw20 message"),
+                    }
+                }
+                _ => panic!("unexpected message"),
+            })
+            .collect();
+        assert_eq!(
+            transfers,
+            vec![
+                ("alice".to_string(), Uint128::new(100)),
+                ("bob".to_string(), Uint128::new(100)),
+                ("carol".to_string(), Uint128::new(102)),
+            ]
+        );
+        let total: Uint128 = transfers.iter().map(|(_, amount)| *amount).sum();
+        assert_eq!(total, Uint128::new(302));
+
+        // the split is validated like the transfers it builds
+        let validated = query_validate_batch(
+            deps.as_ref(),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: OWNER.to_string(),
+                amount: Uint128::new(2),
+                msg: to_binary(&Cw20HookMsg::DistributeCw20Equal {
+                    asset_token: TOKEN.to_string(),
+                    recipients: vec!["alice".to_string(), "bob".to_string(), "x".to_string()],
+                })
+                .unwrap(),
+            }),
+        )
+        .unwrap();
+        assert!(!validated.valid);
+    }
+
+    #[test]
+    fn unresolved_identifiers_are_refunded() {
+        let mut deps = setup(InstantiateMsg {
+            bridge_registry: Some("registry".to_string()),
+            ..instantiate_msg()
+        });
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let RegistryQueryMsg::Resolve { id } = from_binary(msg).unwrap();
+                let address = match id.as_str() {
+                    "eth:alice" => Some("alice".to_string()),
+                    "eth:bob" => Some("bob".to_string()),
+                    "eth:dave" => Some("x".to_string()),
+                    _ => None,
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&ResolveResponse { address }).unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+        let identified = |id: &str, amount: u128| IdentifiedRecipient {
+            id: id.to_string(),
+            amount: Uint128::new(amount),
+        };
+
+        // carol is not registered and dave resolves to an invalid address
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(375, DENOM)),
+            ExecuteMsg::DistributeByIdentifier {
+                denom: DENOM.to_string(),
+                recipients: vec![
+                    identified("eth:alice", 100),
+                    identified("eth:bob", 200),
+                    identified("eth:carol", 50),
+                    identified("eth:dave", 25),
+                ],
+                failure_policy: FailurePolicy::SkipInvalid,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                ("bob".to_string(), coins(200, DENOM)),
+                (OWNER.to_string(), coins(75, DENOM)),
+            ]
+        );
+        assert_eq!(attribute(&response, "unresolved"), Some("eth:carol"));
+        assert_eq!(attribute(&response, "skipped"), Some("x"));
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(50, DENOM)),
+            ExecuteMsg::DistributeByIdentifier {
+                denom: DENOM.to_string(),
+                recipients: vec![identified("eth:carol", 50)],
+                failure_policy: FailurePolicy::SkipInvalid,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![(OWNER.to_string(), coins(50, DENOM))]
+        );
+
+        // nothing unresolved, nothing reported
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            ExecuteMsg::DistributeByIdentifier {
+                denom: DENOM.to_string(),
+                recipients: vec![identified("eth:alice", 100)],
+                failure_policy: FailurePolicy::SkipInvalid,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![("alice".to_string(), coins(100, DENOM))]
+        );
+        assert_eq!(attribute(&response, "unresolved"), None);
+        assert_eq!(attribute(&response, "skipped"), None);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(150, DENOM)),
+            ExecuteMsg::DistributeByIdentifier {
+                denom: DENOM.to_string(),
+                recipients: vec![identified("eth:alice", 100), identified("eth:carol", 50)],
+                failure_policy: FailurePolicy::AbortAll,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Generic(_)));
+    }
+
+    #[test]
+    fn escrow_is_claimable_after_window_and_clawed_back_during_it() {
+        let mut deps = setup(instantiate_msg());
+        let clawback_msg = |dispute_window: u64| ExecuteMsg::DistributeWithClawback {
+            denom: DENOM.to_string(),
+            recipients: vec![recipient("alice", 100)],
+            dispute_window,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            clawback_msg(100),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            clawback_msg(100),
+        )
+        .unwrap();
+        assert_eq!(
+            query_total_escrowed(deps.as_ref(), Some(DENOM.to_string()))
+                .unwrap()
+                .escrowed,
+            coins(200, DENOM)
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::ClaimAfterWindow { id: 0 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DisputeWindowOpen {}));
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::Clawback { id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![(OWNER.to_string(), coins(100, DENOM))]
+        );
+        assert_eq!(
+            query_total_escrowed(deps.as_ref(), Some(DENOM.to_string()))
+                .unwrap()
+                .escrowed,
+            coins(100, DENOM)
+        );
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::Clawback { id: 0 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DisputeWindowClosed {}));
+        let response = execute(
+            deps.as_mut(),
+            env,
+            mock_info("alice", &[]),
+            ExecuteMsg::ClaimAfterWindow { id: 0 },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![("alice".to_string(), coins(100, DENOM))]
+        );
+        assert_eq!(
+            query_total_escrowed(deps.as_ref(), Some(DENOM.to_string()))
+                .unwrap()
+                .escrowed,
+            vec![Coin::new(0, DENOM)]
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            clawback_msg(u64::MAX),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Generic(_)));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            ExecuteMsg::DistributeWithClawback {
+                denom: DENOM.to_string(),
+                recipients: vec![Recipient {
+                    deferred: true,
+                    ..recipient("alice", 100)
+                }],
+                dispute_window: 100,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedDeferred {}));
+    }
+
+    #[test]
+    fn balance_share_distributes_half_of_the_balance() {
+        let mut deps = setup(instantiate_msg());
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(1_000, DENOM));
+        let balance_share_msg = || ExecuteMsg::DistributeBalanceShare {
+            denom: DENOM.to_string(),
+            recipients: vec![weighted("alice", 1), weighted("bob", 3)],
+            share_bps: 5_000,
+        };
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            balance_share_msg(),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(125, DENOM)),
+                ("bob".to_string(), coins(375, DENOM)),
+            ]
+        );
+        assert_eq!(attribute(&response, "free_balance"), Some("1000"));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            balance_share_msg(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnexpectedFunds {}));
+
+        // escrowed amounts are not part of the free balance
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(200, DENOM)),
+            ExecuteMsg::DistributeWithClawback {
+                denom: DENOM.to_string(),
+                recipients: vec![recipient("carol", 200)],
+                dispute_window: 100,
+            },
+        )
+        .unwrap();
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            balance_share_msg(),
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "free_balance"), Some("800"));
+    }
+
+    #[test]
+    fn shares_below_the_minimum_are_redistributed() {
+        let (recipients, below_minimum) = split_with_minimums(
+            Uint128::new(1_000),
+            vec![
+                WeightedRecipient {
+                    min_accept: Some(Uint128::new(300)),
+                    ..weighted("alice", 1)
+                },
+                weighted("bob", 1),
+                weighted("carol", 2),
+            ],
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            recipients,
+            vec![recipient("bob", 333), recipient("carol", 667)]
+        );
+        assert_eq!(below_minimum, vec!["alice".to_string()]);
+
+        let mut deps = setup(instantiate_msg());
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(1_000, DENOM));
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::DistributeBalanceShare {
+                denom: DENOM.to_string(),
+                recipients: vec![
+                    WeightedRecipient {
+                        min_accept: Some(Uint128::new(300)),
+                        ..weighted("alice", 1)
+                    },
+                    weighted("bob", 1),
+                ],
+                share_bps: 5_000,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![("bob".to_string(), coins(500, DENOM))]
+        );
+        assert_eq!(attribute(&response, "below_minimum"), Some("alice"));
+    }
+
+    #[test]
+    fn clean_recipients_merges_and_drops_messy_entries() {
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::CleanRecipients {
+                recipients: vec![
+                    recipient("bob", 50),
+                    recipient("x", 10),
+                    recipient("alice", 100),
+                    recipient("carol", 0),
+                    recipient("bob", 25),
+                    recipient("Invalid Address", 5),
+                ],
+            },
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "action"), Some("clean_recipients"));
+        assert_eq!(attribute(&response, "recipients"), Some("bob:75,alice:100"));
+        assert_eq!(attribute(&response, "num_recipients"), Some("2"));
+        assert_eq!(attribute(&response, "invalid"), Some("x,Invalid Address"));
+        assert!(response.messages.is_empty());
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::CleanRecipients {
+                recipients: vec![recipient("alice", 100)],
+            },
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "invalid"), None);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::CleanRecipients {
+                recipients: vec![recipient("alice", u128::MAX), recipient("alice", 1)],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+    }
+
+    #[test]
+    fn multi_asset_atomicity_with_a_failing_asset() {
+        let mut deps = setup(instantiate_msg());
+        let multi_asset_msg = |atomic: bool| ExecuteMsg::DistributeMultiAsset {
+            recipients: vec![multi_recipient("alice", 100)],
+            atomic,
+        };
+        let funds = vec![Coin::new(100, DENOM), Coin::new(100, "uusd")];
+
+        // one transfer carries every asset, so a failing asset reverts them all
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &funds),
+            multi_asset_msg(true),
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(response.messages[0].reply_on, ReplyOn::Never);
+        assert_eq!(
+            bank_sends(&response),
+            vec![("alice".to_string(), funds.clone())]
+        );
+
+        // every asset is transferred on its own and reports its outcome
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &funds),
+            multi_asset_msg(false),
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 2);
+        assert!(response
+            .messages
+            .iter()
+            .all(|msg| msg.reply_on == ReplyOn::Always));
+        let ids: Vec<u64> = response.messages.iter().map(|msg| msg.id).collect();
+
+        let response = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: ids[0],
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "succeeded_recipient"), Some("alice"));
+        assert_eq!(attribute(&response, "succeeded_denom"), Some(DENOM));
+        assert_eq!(attribute(&response, "succeeded_amount"), Some("100"));
+
+        let response = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: ids[1],
+                result: SubMsgResult::Err("insufficient funds".to_string()),
+            },
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "failed_recipient"), Some("alice"));
+        assert_eq!(attribute(&response, "failed_denom"), Some("uusd"));
+        assert_eq!(attribute(&response, "failed_amount"), Some("100"));
+        assert_eq!(
+            query_recipient_total(deps.as_ref(), "alice".to_string(), Some("uusd".to_string()))
+                .unwrap()
+                .totals,
+            vec![AssetAmount {
+                asset: "uusd".to_string(),
+                amount: Uint128::new(100),
+            }]
+        );
+
+        // the failed coin is reserved for the sender to reclaim
+        assert_eq!(
+            query_total_escrowed(deps.as_ref(), Some("uusd".to_string()))
+                .unwrap()
+                .escrowed,
+            vec![Coin::new(100, "uusd")]
+        );
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::ReclaimFailed {
+                denom: "uusd".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![(OWNER.to_string(), coins(100, "uusd"))]
+        );
+    }
+
+    #[test]
+    fn storage_estimate_scales_linearly_with_recipients() {
+        let deps = setup(instantiate_msg());
+        for &escrowed in &[false, true] {
+            let bytes = |recipients: u32| {
+                query_storage_estimate(deps.as_ref(), recipients, escrowed)
+                    .unwrap()
+                    .bytes
+            };
+            assert_eq!(bytes(0), 0);
+            assert!(bytes(1) > 0);
+            assert_eq!(bytes(10), 10 * bytes(1));
+            assert_eq!(bytes(100), 10 * bytes(10));
+        }
+    }
+
+    #[test]
+    fn event_verbosity_bounds_attributes_and_events() {
+        for level in &[EventLevel::Minimal, EventLevel::Summary, EventLevel::Full] {
+            let mut deps = setup(InstantiateMsg {
+                event_verbosity: level.clone(),
+                ..instantiate_msg()
+            });
+            let response = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(OWNER, &coins(100, DENOM)),
+                ExecuteMsg::DistributeNative {
+                    denom: DENOM.to_string(),
+                    recipients: vec![recipient("alice", 100), recipient("x", 200)],
+                    failure_policy: FailurePolicy::SkipInvalid,
+                    best_effort: false,
+                    gas_reserve_denom: None,
+                    display_decimals: Some(6),
+                    nonce: None,
+                },
+            )
+            .unwrap();
+            let keys: Vec<&str> = response
+                .attributes
+                .iter()
+                .map(|attribute| attribute.key.as_str())
+                .collect();
+            match level {
+                EventLevel::Minimal => {
+                    assert_eq!(keys, vec!["action"]);
+                    assert!(response.events.is_empty());
+                }
+                EventLevel::Summary => {
+                    assert_eq!(
+                        keys,
+                        vec![
+                            "action",
+                            "denom",
+                            "total_amount",
+                            "num_recipients",
+                            "skipped"
+                        ]
+                    );
+                    assert!(response.events.is_empty());
+                }
+                EventLevel::Full => {
+                    assert_eq!(attribute(&response, "skipped"), Some("x"));
+                    assert_eq!(response.events.len(), 2);
+                }
+            }
+
+            let response = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(OWNER, &coins(300, DENOM)),
+                ExecuteMsg::DistributeConditional {
+                    denom: DENOM.to_string(),
+                    recipients: vec![recipient("alice", 100), recipient("bob", 200)],
+                    conditions: vec![("alice".to_string(), true), ("bob".to_string(), false)],
+                },
+            )
+            .unwrap();
+            assert_eq!(
+                attribute(&response, "refund"),
+                match level {
+                    EventLevel::Minimal => None,
+                    _ => Some("200"),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn rewards_reconcile_with_the_balance_increases() {
+        let mut deps = setup(instantiate_msg());
+        let balances = |amounts: [u128; 3]| -> Vec<(String, Uint128)> {
+            vec!["alice", "bob", "carol"]
+                .into_iter()
+                .zip(amounts.iter())
+                .map(|(address, amount)| (address.to_string(), Uint128::new(*amount)))
+                .collect()
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(900, DENOM)),
+            ExecuteMsg::DistributeByDiff {
+                reward_denom: DENOM.to_string(),
+                start_balances: balances([100, 500, 300]),
+                end_balances: balances([200, 400, 500]),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(300, DENOM)),
+                ("carol".to_string(), coins(600, DENOM)),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_afford_checks_the_free_balance() {
+        let mut deps = setup(instantiate_msg());
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(300, DENOM));
+        for &(amount, can_afford) in &[(300u128, true), (301, false)] {
+            assert_eq!(
+                query_can_afford(
+                    deps.as_ref(),
+                    mock_env(),
+                    DENOM.to_string(),
+                    Uint128::new(amount)
+                )
+                .unwrap(),
+                CanAffordResponse {
+                    can_afford,
+                    available: Uint128::new(300),
+                }
+            );
+        }
+
+        // reserved funds are not free to distribute
+        RESERVED
+            .save(deps.as_mut().storage, DENOM, &Uint128::new(100))
+            .unwrap();
+        for &(amount, can_afford) in &[(200u128, true), (201, false)] {
+            assert_eq!(
+                query_can_afford(
+                    deps.as_ref(),
+                    mock_env(),
+                    DENOM.to_string(),
+                    Uint128::new(amount)
+                )
+                .unwrap(),
+                CanAffordResponse {
+                    can_afford,
+                    available: Uint128::new(200),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn nonces_must_be_used_in_order() {
+        let mut deps = setup(instantiate_msg());
+        let mut distribute = |nonce: u64| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(OWNER, &coins(100, DENOM)),
+                ExecuteMsg::DistributeNative {
+                    denom: DENOM.to_string(),
+                    recipients: vec![recipient("alice", 100)],
+                    failure_policy: FailurePolicy::AbortAll,
+                    best_effort: false,
+                    gas_reserve_denom: None,
+                    display_decimals: None,
+                    nonce: Some(nonce),
+                },
+            )
+        };
+
+        distribute(0).unwrap();
+        distribute(1).unwrap();
+        let err = distribute(1).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidNonce {
+                expected: 2,
+                got: 1
+            }
+        ));
+        let err = distribute(3).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidNonce {
+                expected: 2,
+                got: 3
+            }
+        ));
+        distribute(2).unwrap();
+    }
+
+    #[test]
+    fn sweep_swaps_two_dust_balances_and_sends_the_proceeds() {
+        let mut deps = setup(instantiate_msg());
+        deps.querier.update_balance(
+            MOCK_CONTRACT_ADDR,
+            vec![
+                Coin::new(105, DENOM),
+                Coin::new(3, "uusd"),
+                Coin::new(1_000, "ukrw"),
+            ],
+        );
+        // reserved funds are not dust
+        RESERVED
+            .save(deps.as_mut().storage, DENOM, &Uint128::new(100))
+            .unwrap();
+        let sweep_msg = ExecuteMsg::SweepDustToAsset {
+            assets: vec![DENOM.to_string(), "uusd".to_string(), "ueur".to_string()],
+            target_denom: "ukrw".to_string(),
+            swap_contract: "swap".to_string(),
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mallory", &[]),
+            sweep_msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let response =
+            execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), sweep_msg).unwrap();
+        assert_eq!(attribute(&response, "swept"), Some("5uluna,3uusd"));
+        let swap = |coin: Coin| {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "swap".to_string(),
+                msg: to_binary(&SwapExecuteMsg::Swap {
+                    ask_denom: "ukrw".to_string(),
+                })
+                .unwrap(),
+                funds: vec![coin],
+            })
+        };
+        assert_eq!(
+            response.messages,
+            vec![
+                SubMsg::new(swap(Coin::new(5, DENOM))),
+                SubMsg::reply_on_success(swap(Coin::new(3, "uusd")), SWAP_REPLY_ID),
+            ]
+        );
+
+        // the mocked swap contract converted both balances into 40ukrw
+        deps.querier.update_balance(
+            MOCK_CONTRACT_ADDR,
+            vec![Coin::new(100, DENOM), Coin::new(1_040, "ukrw")],
+        );
+        let response = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: SWAP_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "proceeds"), Some("40"));
+        assert_eq!(
+            bank_sends(&response),
+            vec![(OWNER.to_string(), coins(40, "ukrw"))]
+        );
+        assert!(PENDING_SWAP.may_load(&deps.storage).unwrap().is_none());
+
+        // nothing is left to sweep
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::SweepDustToAsset {
+                assets: vec![DENOM.to_string()],
+                target_denom: "ukrw".to_string(),
+                swap_contract: "swap".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Generic(_)));
+    }
+
+    #[test]
+    fn clean_recipients_applies_address_format() {
+        let bech32 = "terra1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5lzv7xu";
+        let eth = "0x52908400098527886e0f7030069857d2e4169ee7";
+        for (address_format, valid, invalid) in [
+            (AddressFormat::Bech32, bech32, eth),
+            (AddressFormat::Ethereum, eth, bech32),
+        ] {
+            let mut deps = setup(InstantiateMsg {
+                address_format,
+                ..instantiate_msg()
+            });
+            let response = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("anyone", &[]),
+                ExecuteMsg::CleanRecipients {
+                    recipients: vec![recipient(valid, 100), recipient(invalid, 50)],
+                },
+            )
+            .unwrap();
+            assert_eq!(
+                attribute(&response, "recipients"),
+                Some(format!("{}:100", valid).as_str())
+            );
+            assert_eq!(attribute(&response, "invalid"), Some(invalid));
+        }
+
+        let mut deps = setup(instantiate_msg());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::CleanRecipients {
+                recipients: vec![recipient(bech32, 100), recipient(eth, 50)],
+            },
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "num_recipients"), Some("2"));
+        assert_eq!(attribute(&response, "invalid"), None);
+    }
+
+    #[test]
+    fn distributions_check_the_address_format() {
+        let bech32 = "terra1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5lzv7xu";
+        let eth = "0x52908400098527886e0f7030069857d2e4169ee7";
+        let mut deps = setup(InstantiateMsg {
+            address_format: AddressFormat::Bech32,
+            ..instantiate_msg()
+        });
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(150, DENOM)),
+            distribute_native_msg(vec![recipient(bech32, 100), recipient(eth, 50)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAddressFormat { .. }));
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: OWNER.to_string(),
+                amount: Uint128::new(100),
+                msg: to_binary(&Cw20HookMsg::DistributeCw20 {
+                    asset_token: TOKEN.to_string(),
+                    recipients: vec![recipient(bech32, 100), recipient(eth, 50)],
+                    failure_policy: FailurePolicy::SkipInvalid,
+                    nonce: None,
+                    output: OutputKind::SameToken,
+                })
+                .unwrap(),
+            }),
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "skipped"), Some(eth));
+
+        let response = query_validate_batch(
+            deps.as_ref(),
+            distribute_native_msg(vec![recipient(eth, 50)]),
+        )
+        .unwrap();
+        assert!(!response.valid);
+    }
+
+    #[test]
+    fn release_forecast_buckets_escrows() {
+        let mut deps = setup(instantiate_msg());
+        for &(amount, dispute_window) in &[(100, 10), (200, 60), (400, 500)] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(OWNER, &coins(amount, DENOM)),
+                ExecuteMsg::DistributeWithClawback {
+                    denom: DENOM.to_string(),
+                    recipients: vec![recipient("alice", amount)],
+                    dispute_window,
+                },
+            )
+            .unwrap();
+        }
+
+        let forecast =
+            query_release_forecast(deps.as_ref(), mock_env(), DENOM.to_string(), 100, 2).unwrap();
+        let now = mock_env().block.time;
+        assert_eq!(
+            forecast.buckets,
+            vec![
+                ReleaseBucket {
+                    start: now,
+                    end: now.plus_seconds(50),
+                    amount: Uint128::new(100),
+                },
+                ReleaseBucket {
+                    start: now.plus_seconds(50),
+                    end: now.plus_seconds(100),
+                    amount: Uint128::new(200),
+                },
+            ]
+        );
+
+        query_release_forecast(deps.as_ref(), mock_env(), DENOM.to_string(), u64::MAX, 2)
+            .unwrap_err();
+        query_release_forecast(deps.as_ref(), mock_env(), DENOM.to_string(), 100, u32::MAX)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn opt_in_required_on_every_payout_and_claim() {
+        let mut deps = setup(InstantiateMsg {
+            require_opt_in: true,
+            ..instantiate_msg()
+        });
+
+        // offered before opting in, claimable only after
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100)]),
+        )
+        .unwrap();
+        assert!(bank_sends(&response).is_empty());
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(50, DENOM), Coin::new(70, "uusd")]),
+            ExecuteMsg::DistributeMultiAsset {
+                recipients: vec![MultiRecipient {
+                    recipient: "alice".to_string(),
+                    coins: vec![Coin::new(50, DENOM), Coin::new(70, "uusd")],
+                }],
+                atomic: true,
+            },
+        )
+        .unwrap();
+        assert!(bank_sends(&response).is_empty());
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::ClaimDeferred {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotOptedIn { .. }));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::OptIn {},
+        )
+        .unwrap();
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::ClaimDeferred {},
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![(
+                "alice".to_string(),
+                vec![Coin::new(150, DENOM), Coin::new(70, "uusd")]
+            )]
+        );
+
+        // CW20 tokens cannot be deferred
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            distribute_cw20_msg(OWNER, vec![recipient("bob", 100)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotOptedIn { .. }));
+        let response = query_validate_batch(
+            deps.as_ref(),
+            distribute_cw20_msg(OWNER, vec![recipient("bob", 100)]),
+        )
+        .unwrap();
+        assert!(!response.valid);
+
+        // preferred payouts are deferred in the resolved denom
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, "uusd")),
+            ExecuteMsg::DistributePreferred {
+                default_denom: DENOM.to_string(),
+                recipients: vec![PreferredRecipient {
+                    recipient: "bob".to_string(),
+                    amount: Uint128::new(100),
+                    preferred_denom: Some("uusd".to_string()),
+                }],
+            },
+        )
+        .unwrap();
+        assert!(bank_sends(&response).is_empty());
+        assert_eq!(
+            CLAIMABLE
+                .load(&deps.storage, (&Addr::unchecked("bob"), "uusd"))
+                .unwrap(),
+            Uint128::new(100)
+        );
+
+        // escrows are claimable only after opting in
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            ExecuteMsg::DistributeWithClawback {
+                denom: DENOM.to_string(),
+                recipients: vec![recipient("bob", 100)],
+                dispute_window: 0,
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::ClaimAfterWindow { id: 0 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotOptedIn { .. }));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::OptIn {},
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::ClaimAfterWindow { id: 0 },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn approval_hook_approves_or_rejects_distributions() {
+        let mut deps = setup(InstantiateMsg {
+            approval_hook: Some("hook".to_string()),
+            ..instantiate_msg()
+        });
+        // the hook only approves distributions of at most 100 per recipient
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let ApprovalQueryMsg::Approve { recipients, .. } = from_binary(msg).unwrap();
+                let approved = recipients
+                    .iter()
+                    .all(|recipient| recipient.amount <= Uint128::new(100));
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&ApprovalResponse { approved }).unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100)]),
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(101, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 101)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotApproved {}));
+
+        // the batch validation asks the hook too
+        let response = query_validate_batch(
+            deps.as_ref(),
+            distribute_native_msg(vec![recipient("alice", 101)]),
+        )
+        .unwrap();
+        assert!(!response.valid);
+
+        // every denom of a multi-asset distribution is its own plan
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(101, DENOM), Coin::new(101, "uusd")]),
+            ExecuteMsg::DistributeMultiAsset {
+                recipients: vec![multi_recipient("alice", 101)],
+                atomic: true,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotApproved {}));
+    }
+
+    #[test]
+    fn lifetime_cap_counts_deferred_and_escrowed_amounts() {
+        let mut deps = setup(InstantiateMsg {
+            lifetime_cap: Some(Uint128::new(100)),
+            ..instantiate_msg()
+        });
+        let mut distribute = |recipients: Vec<Recipient>| {
+            let total: Uint128 = recipients.iter().map(|recipient| recipient.amount).sum();
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(OWNER, &coins(total.u128(), DENOM)),
+                distribute_native_msg(recipients),
+            )
+        };
+
+        distribute(vec![recipient("alice", 60)]).unwrap();
+        distribute(vec![recipient("alice", 40)]).unwrap();
+        let err = distribute(vec![recipient("alice", 1)]).unwrap_err();
+        assert!(matches!(err, ContractError::LifetimeCapExceeded { .. }));
+
+        distribute(vec![Recipient {
+            deferred: true,
+            ..recipient("bob", 60)
+        }])
+        .unwrap();
+        let err = distribute(vec![recipient("bob", 50)]).unwrap_err();
+        assert!(matches!(err, ContractError::LifetimeCapExceeded { .. }));
+
+        let mut deps = setup(InstantiateMsg {
+            lifetime_cap: Some(Uint128::new(100)),
+            ..instantiate_msg()
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(60, DENOM)),
+            ExecuteMsg::DistributeWithClawback {
+                denom: DENOM.to_string(),
+                recipients: vec![recipient("carol", 60)],
+                dispute_window: 100,
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(50, DENOM)),
+            distribute_native_msg(vec![recipient("carol", 50)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::LifetimeCapExceeded { .. }));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(50, DENOM)),
+            ExecuteMsg::DistributeWithClawback {
+                denom: DENOM.to_string(),
+                recipients: vec![recipient("carol", 50)],
+                dispute_window: 100,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::LifetimeCapExceeded { .. }));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(40, DENOM)),
+            distribute_native_msg(vec![recipient("carol", 40)]),
+        )
+        .unwrap();
+
+        // the batch validation checks the cap too
+        let response = query_validate_batch(
+            deps.as_ref(),
+            distribute_native_msg(vec![recipient("carol", 1)]),
+        )
+        .unwrap();
+        assert!(!response.valid);
+
+        // a multi-asset distribution checks every coin, deferred or not
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(101, DENOM), Coin::new(101, "uusd")]),
+            ExecuteMsg::DistributeMultiAsset {
+                recipients: vec![multi_recipient("dave", 101)],
+                atomic: true,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::LifetimeCapExceeded { .. }));
+    }
+
+    #[test]
+    fn simulate_batch_reports_a_later_operation_over_a_limit() {
+        let mut deps = setup(InstantiateMsg {
+            lifetime_cap: Some(Uint128::new(150)),
+            ..instantiate_msg()
+        });
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(1_000, DENOM));
+        let op = |recipients: Vec<Recipient>| SimOp {
+            denom: DENOM.to_string(),
+            recipients,
+        };
+        let response = query_simulate_batch(
+            deps.as_ref(),
+            mock_env(),
+            vec![
+                op(vec![recipient("alice", 100)]),
+                op(vec![recipient("bob", 100)]),
+                op(vec![recipient("alice", 100)]),
+                op(vec![recipient("carol", 100)]),
+            ],
+        )
+        .unwrap();
+        let failed: Vec<bool> = response
+            .results
+            .iter()
+            .map(|result| result.error.is_some())
+            .collect();
+        assert_eq!(failed, vec![false, false, true, false]);
+        assert_eq!(response.remaining, coins(700, DENOM));
+    }
+
+    #[test]
+    fn distribute_from_balance_withdraws_the_shortfall_from_the_vault() {
+        let mut deps = setup(InstantiateMsg {
+            vault_contract: Some("vault".to_string()),
+            ..instantiate_msg()
+        });
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(150, DENOM));
+        // reserved funds do not cover the distribution
+        RESERVED
+            .save(deps.as_mut().storage, DENOM, &Uint128::new(100))
+            .unwrap();
+        let from_balance_msg = ExecuteMsg::DistributeFromBalance {
+            denom: DENOM.to_string(),
+            recipients: vec![recipient("alice", 60), recipient("bob", 40)],
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mallory", &[]),
+            from_balance_msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            from_balance_msg,
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "shortfall"), Some("50"));
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::reply_on_success(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "vault".to_string(),
+                    msg: to_binary(&VaultExecuteMsg::Withdraw {
+                        denom: DENOM.to_string(),
+                        amount: Uint128::new(50),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                VAULT_REPLY_ID,
+            )]
+        );
+
+        // the mocked vault sent the shortfall
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(200, DENOM));
+        let response = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: VAULT_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(60, DENOM)),
+                ("bob".to_string(), coins(40, DENOM)),
+            ]
+        );
+        assert!(PENDING_VAULT.may_load(&deps.storage).unwrap().is_none());
+    }
+
+    #[test]
+    fn distribute_from_balance_fails_if_the_vault_leaves_a_shortfall() {
+        let mut deps = setup(InstantiateMsg {
+            vault_contract: Some("vault".to_string()),
+            ..instantiate_msg()
+        });
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(50, DENOM));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::DistributeFromBalance {
+                denom: DENOM.to_string(),
+                recipients: vec![recipient("alice", 60), recipient("bob", 40)],
+            },
+        )
+        .unwrap();
+
+        // the mocked vault only sent part of the shortfall
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(80, DENOM));
+        let err = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: VAULT_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::VaultShortfall { shortfall, .. } if shortfall == Uint128::new(20)
+        ));
+
+        // without a vault the balance must cover the recipients
+        let mut deps = setup(instantiate_msg());
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(150, DENOM));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::DistributeFromBalance {
+                denom: DENOM.to_string(),
+                recipients: vec![recipient("alice", 160)],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFunds { .. }));
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::DistributeFromBalance {
+                denom: DENOM.to_string(),
+                recipients: vec![recipient("alice", 150)],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![("alice".to_string(), coins(150, DENOM))]
+        );
+    }
+
+    #[test]
+    fn frozen_recipient_cannot_claim() {
+        let mut deps = setup(instantiate_msg());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            distribute_native_msg(vec![Recipient {
+                deferred: true,
+                ..recipient("alice", 100)
+            }]),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            ExecuteMsg::DistributeWithClawback {
+                denom: DENOM.to_string(),
+                recipients: vec![recipient("alice", 100)],
+                dispute_window: 0,
+            },
+        )
+        .unwrap();
+        let set_frozen = |frozen: bool| {
+            let address = "alice".to_string();
+            if frozen {
+                ExecuteMsg::FreezeRecipient { address }
+            } else {
+                ExecuteMsg::UnfreezeRecipient { address }
+            }
+        };
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            set_frozen(true),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::ClaimDeferred {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RecipientFrozen { .. }));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::ClaimAfterWindow { id: 0 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RecipientFrozen { .. }));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            set_frozen(false),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::ClaimDeferred {},
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::ClaimAfterWindow { id: 0 },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn frozen_recipients_cannot_receive_distributions() {
+        let mut deps = setup(instantiate_msg());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::FreezeRecipient {
+                address: "mallory".to_string(),
+            },
+        )
+        .unwrap();
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100)]),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![("alice".to_string(), coins(100, DENOM))]
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(150, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100), recipient("mallory", 50)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RecipientFrozen { .. }));
+        let multi_asset_msg = ExecuteMsg::DistributeMultiAsset {
+            recipients: vec![multi_recipient("mallory", 50)],
+            atomic: true,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(50, DENOM), Coin::new(50, "uusd")]),
+            multi_asset_msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RecipientFrozen { .. }));
+        assert!(!validate(deps.as_ref(), multi_asset_msg).valid);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::UnfreezeRecipient {
+                address: "mallory".to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(150, DENOM)),
+            distribute_native_msg(vec![recipient("alice", 100), recipient("mallory", 50)]),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn currency_amounts_are_converted_at_the_configured_rate() {
+        let currency = |currency: &str, rate: &str| CurrencyRate {
+            currency: currency.to_string(),
+            denom: DENOM.to_string(),
+            rate: rate.parse().unwrap(),
+        };
+        let mut deps = setup(InstantiateMsg {
+            currencies: vec![currency("usd", "1.5")],
+            ..instantiate_msg()
+        });
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(151, DENOM)),
+            ExecuteMsg::DistributeInCurrency {
+                currency: "usd".to_string(),
+                recipients: vec![
+                    ("alice".to_string(), Uint128::new(100)),
+                    ("bob".to_string(), Uint128::new(1)),
+                ],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(150, DENOM)),
+                ("bob".to_string(), coins(1, DENOM)),
+            ]
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            ExecuteMsg::DistributeInCurrency {
+                currency: "usd".to_string(),
+                recipients: vec![("alice".to_string(), Uint128::MAX)],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+
+        for currencies in [
+            vec![currency("usd", "0")],
+            vec![currency("usd", "1"), currency("usd", "2")],
+        ] {
+            let err = instantiate(
+                mock_dependencies().as_mut(),
+                mock_env(),
+                mock_info(OWNER, &[]),
+                InstantiateMsg {
+                    currencies,
+                    ..instantiate_msg()
+                },
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::Generic(_)));
+        }
+    }
+
+    #[test]
+    fn matching_pool_follows_the_quadratic_funding_formula() {
+        let mut deps = setup(instantiate_msg());
+        let contributions = |amounts: Vec<u128>| -> Vec<Uint128> {
+            amounts.into_iter().map(Uint128::new).collect()
+        };
+        // (sqrt(4) + sqrt(9))^2 = 25 against sqrt(16)^2 = 16
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(410, DENOM)),
+            ExecuteMsg::DistributeQuadratic {
+                denom: DENOM.to_string(),
+                contributions: vec![
+                    ("alice".to_string(), contributions(vec![4, 9])),
+                    ("bob".to_string(), contributions(vec![16])),
+                ],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(250, DENOM)),
+                ("bob".to_string(), coins(160, DENOM)),
+            ]
+        );
+    }
+
+    #[test]
+    fn message_schema_lists_the_execute_messages() {
+        let execute = query_message_schema().execute;
+        let names: Vec<&str> = execute
+            .iter()
+            .map(|message| message.name.as_str())
+            .collect();
+        for name in [
+            "receive",
+            "distribute_native",
+            "distribute_multi_asset",
+            "update_owner",
+            "claim_deferred",
+            "distribute_with_compound",
+        ] {
+            assert!(names.contains(&name), "missing {}", name);
+        }
+
+        let distribute_native = execute
+            .iter()
+            .find(|message| message.name == "distribute_native")
+            .unwrap();
+        assert_eq!(
+            distribute_native.required,
+            vec!["denom".to_string(), "recipients".to_string()]
+        );
+    }
+
+    #[test]
+    fn cw20_distribution_pays_the_same_token_or_unwraps_to_native() {
+        let mut deps = setup(InstantiateMsg {
+            unwrap_adapter: Some("adapter".to_string()),
+            ..instantiate_msg()
+        });
+        let cw20_msg = |output: OutputKind| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: OWNER.to_string(),
+                amount: Uint128::new(100),
+                msg: to_binary(&Cw20HookMsg::DistributeCw20 {
+                    asset_token: TOKEN.to_string(),
+                    recipients: vec![recipient("alice", 60), recipient("bob", 40)],
+                    failure_policy: FailurePolicy::AbortAll,
+                    nonce: None,
+                    output,
+                })
+                .unwrap(),
+            })
+        };
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            cw20_msg(OutputKind::SameToken),
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 2);
+        assert!(bank_sends(&response).is_empty());
+
+        let native_msg = cw20_msg(OutputKind::Native(DENOM.to_string()));
+        assert_eq!(
+            validate(deps.as_ref(), native_msg.clone()),
+            ValidateBatchResponse {
+                valid: true,
+                error: None,
+                required_funds: vec![],
+            }
+        );
+        let response =
+            execute(deps.as_mut(), mock_env(), mock_info(TOKEN, &[]), native_msg).unwrap();
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::reply_on_success(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: TOKEN.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        contract: "adapter".to_string(),
+                        amount: Uint128::new(100),
+                        msg: to_binary(&UnwrapHookMsg::Unwrap {
+                            denom: DENOM.to_string(),
+                        })
+                        .unwrap(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                UNWRAP_REPLY_ID,
+            )]
+        );
+
+        // the mocked adapter sent one coin per token
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, DENOM));
+        let response = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: UNWRAP_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
+        assert_eq!(attribute(&response, "unwrapped_amount"), Some("100"));
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(60, DENOM)),
+                ("bob".to_string(), coins(40, DENOM)),
+            ]
+        );
+        assert!(PENDING_UNWRAP.may_load(&deps.storage).unwrap().is_none());
+    }
+
+    #[test]
+    fn unwrapped_amount_must_match_the_sent_tokens() {
+        let native_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: OWNER.to_string(),
+            amount: Uint128::new(100),
+            msg: to_binary(&Cw20HookMsg::DistributeCw20 {
+                asset_token: TOKEN.to_string(),
+                recipients: vec![recipient("alice", 100)],
+                failure_policy: FailurePolicy::AbortAll,
+                nonce: None,
+                output: OutputKind::Native(DENOM.to_string()),
+            })
+            .unwrap(),
+        });
+
+        // unwrapping needs an adapter
+        let mut deps = setup(instantiate_msg());
+        assert!(!validate(deps.as_ref(), native_msg.clone()).valid);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TOKEN, &[]),
+            native_msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Generic(_)));
+
+        let mut deps = setup(InstantiateMsg {
+            unwrap_adapter: Some("adapter".to_string()),
+            ..instantiate_msg()
+        });
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(10, DENOM));
+        execute(deps.as_mut(), mock_env(), mock_info(TOKEN, &[]), native_msg).unwrap();
+        // the mocked adapter sent fewer coins than tokens
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(109, DENOM));
+        let err = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: UNWRAP_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::UnexpectedUnwrappedAmount { got, .. } if got == Uint128::new(99)
+        ));
+    }
+
+    #[test]
+    fn swapped_distribution_enforces_the_minimum_output() {
+        let swapped_msg = || ExecuteMsg::DistributeSwapped {
+            denom: DENOM.to_string(),
+            recipients: vec![weighted("alice", 3), weighted("bob", 1)],
+            swap_contract: "swap".to_string(),
+            min_output: Uint128::new(100),
+        };
+        let swap_reply = || Reply {
+            id: SWAP_DISTRIBUTION_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+
+        // the mocked swap returned more than the minimum
+        let mut deps = setup(instantiate_msg());
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(10, DENOM));
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(50, "uusd")),
+            swapped_msg(),
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::reply_on_success(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "swap".to_string(),
+                    msg: to_binary(&SwapExecuteMsg::Swap {
+                        ask_denom: DENOM.to_string(),
+                    })
+                    .unwrap(),
+                    funds: coins(50, "uusd"),
+                }),
+                SWAP_DISTRIBUTION_REPLY_ID,
+            )]
+        );
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(130, DENOM));
+        let response = reply(deps.as_mut(), mock_env(), swap_reply()).unwrap();
+        assert_eq!(attribute(&response, "proceeds"), Some("120"));
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(90, DENOM)),
+                ("bob".to_string(), coins(30, DENOM)),
+            ]
+        );
+        assert!(PENDING_SWAP_DISTRIBUTION
+            .may_load(&deps.storage)
+            .unwrap()
+            .is_none());
+
+        // the mocked swap returned less than the minimum
+        let mut deps = setup(instantiate_msg());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(50, "uusd")),
+            swapped_msg(),
+        )
+        .unwrap();
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(99, DENOM));
+        let err = reply(deps.as_mut(), mock_env(), swap_reply()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::SlippageExceeded { min, got }
+                if min == Uint128::new(100) && got == Uint128::new(99)
+        ));
+
+        // the sent coin must differ from the payout denom
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(50, DENOM)),
+            swapped_msg(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Generic(_)));
+    }
+
+    #[test]
+    fn diff_recipients_reports_additions_removals_and_changes() {
+        let response = query_diff_recipients(
+            vec![
+                recipient("alice", 100),
+                recipient("bob", 200),
+                recipient("carol", 300),
+            ],
+            vec![
+                recipient("alice", 100),
+                recipient("carol", 350),
+                recipient("dave", 400),
+            ],
+        );
+        assert_eq!(
+            response,
+            DiffRecipientsResponse {
+                added: vec![recipient("dave", 400)],
+                removed: vec![recipient("bob", 200)],
+                changed: vec![RecipientChange {
+                    recipient: "carol".to_string(),
+                    old_amount: Uint128::new(300),
+                    new_amount: Uint128::new(350),
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn suggested_pages_cover_every_recipient() {
+        let deps = setup(instantiate_msg());
+        for &(recipient_count, gas_target) in &[
+            (1u32, GAS_PER_RECIPIENT),
+            (250, 10 * GAS_PER_RECIPIENT),
+            (1_001, 1_000 * GAS_PER_RECIPIENT),
+        ] {
+            let response =
+                query_suggest_page_size(deps.as_ref(), recipient_count, gas_target).unwrap();
+            assert!(response.page_size <= 100);
+            assert!(response.page_size as u64 * GAS_PER_RECIPIENT <= gas_target);
+            assert!(response.pages * response.page_size >= recipient_count);
+            assert!((response.pages - 1) * response.page_size < recipient_count);
+        }
+
+        let err = query_suggest_page_size(deps.as_ref(), 10, GAS_PER_RECIPIENT - 1).unwrap_err();
+        assert!(matches!(err, StdError::GenericErr { .. }));
+    }
+
+    #[test]
+    fn multi_asset_invalid_address_goes_to_fallback() {
+        let mut deps = setup(InstantiateMsg {
+            fallback_recipient: Some("treasury".to_string()),
+            ..instantiate_msg()
+        });
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[Coin::new(150, DENOM), Coin::new(150, "uusd")]),
+            ExecuteMsg::DistributeMultiAsset {
+                recipients: vec![multi_recipient("alice", 100), multi_recipient("x", 50)],
+                atomic: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                (
+                    "alice".to_string(),
+                    vec![Coin::new(100, DENOM), Coin::new(100, "uusd")]
+                ),
+                (
+                    "treasury".to_string(),
+                    vec![Coin::new(50, DENOM), Coin::new(50, "uusd")]
+                ),
+            ]
+        );
+        assert_eq!(
+            attribute(&response, "redirected"),
+            Some("x:50uluna,x:50uusd")
+        );
+    }
+
+    #[test]
+    fn display_amounts_follow_the_paid_recipients() {
+        let mut deps = setup(InstantiateMsg {
+            fallback_recipient: Some("treasury".to_string()),
+            ..instantiate_msg()
+        });
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(1_734_567, DENOM)),
+            ExecuteMsg::DistributeNative {
+                denom: DENOM.to_string(),
+                recipients: vec![recipient("alice", 1_234_567), recipient("x", 500_000)],
+                failure_policy: FailurePolicy::AbortAll,
+                best_effort: false,
+                gas_reserve_denom: None,
+                display_decimals: Some(6),
+                nonce: None,
+            },
+        )
+        .unwrap();
+        let display_events: Vec<&Event> = response
+            .events
+            .iter()
+            .filter(|event| event.ty == "display_amount")
+            .collect();
+        assert_eq!(
+            display_events,
+            vec![
+                &Event::new("display_amount")
+                    .add_attribute("recipient", "alice")
+                    .add_attribute("amount", "1234567")
+                    .add_attribute("display_amount", "1.234567"),
+                &Event::new("display_amount")
+                    .add_attribute("recipient", "treasury")
+                    .add_attribute("amount", "500000")
+                    .add_attribute("display_amount", "0.500000"),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_address_goes_to_fallback() {
+        let mut deps = setup(InstantiateMsg {
+            fallback_recipient: Some("treasury".to_string()),
+            ..instantiate_msg()
+        });
+        // the redirected amount merges into the fallback's own entry
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(180, DENOM)),
+            distribute_native_msg(vec![
+                recipient("alice", 100),
+                recipient("x", 50),
+                recipient("treasury", 30),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                ("treasury".to_string(), coins(80, DENOM)),
+            ]
+        );
+        assert_eq!(attribute(&response, "redirected"), Some("x:50"));
+        assert_eq!(attribute(&response, "skipped"), None);
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(150, DENOM)),
+            ExecuteMsg::DistributePreferred {
+                default_denom: DENOM.to_string(),
+                recipients: vec![
+                    PreferredRecipient {
+                        recipient: "alice".to_string(),
+                        amount: Uint128::new(100),
+                        preferred_denom: None,
+                    },
+                    PreferredRecipient {
+                        recipient: "x".to_string(),
+                        amount: Uint128::new(50),
+                        preferred_denom: None,
+                    },
+                ],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(100, DENOM)),
+                ("treasury".to_string(), coins(50, DENOM)),
+            ]
+        );
+        assert_eq!(attribute(&response, "redirected"), Some("x:50"));
+    }
+
+    #[test]
+    fn compound_splits_direct_and_staked_amounts() {
+        let mut deps = setup(instantiate_msg());
+        let compound_msg = |recipients: Vec<Recipient>| ExecuteMsg::DistributeWithCompound {
+            denom: DENOM.to_string(),
+            recipients,
+            compound_bps: 3000,
+            staking_contract: "staking".to_string(),
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            compound_msg(vec![recipient("alice", 100)]),
+        )
+        .unwrap();
+        assert_eq!(
+            response
+                .messages
+                .iter()
+                .map(|msg| msg.msg.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "alice".to_string(),
+                    amount: coins(70, DENOM),
+                }),
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "staking".to_string(),
+                    funds: coins(30, DENOM),
+                    msg: to_binary(&StakingExecuteMsg::StakeFor {
+                        recipient: "alice".to_string(),
+                    })
+                    .unwrap(),
+                }),
+            ]
+        );
+        assert_eq!(attribute(&response, "compounded_amount"), Some("30"));
+        assert_eq!(response.events.len(), 1);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            compound_msg(vec![Recipient {
+                deferred: true,
+                ..recipient("alice", 100)
+            }]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedDeferred {}));
+    }
+
+    #[test]
+    fn compound_recipients_are_checked_like_native_ones() {
+        let mut deps = setup(InstantiateMsg {
+            require_opt_in: true,
+            lifetime_cap: Some(Uint128::new(100)),
+            fallback_recipient: Some("treasury".to_string()),
+            ..instantiate_msg()
+        });
+        let compound_msg = |recipients: Vec<Recipient>| ExecuteMsg::DistributeWithCompound {
+            denom: DENOM.to_string(),
+            recipients,
+            compound_bps: 5000,
+            staking_contract: "staking".to_string(),
+        };
+        for address in ["alice", "treasury"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(address, &[]),
+                ExecuteMsg::OptIn {},
+            )
+            .unwrap();
+        }
+
+        // a stake cannot be deferred for recipients that did not opt in
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            compound_msg(vec![recipient("bob", 100)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotOptedIn { .. }));
+
+        // invalid addresses are redirected to the fallback
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(60, DENOM)),
+            compound_msg(vec![recipient("alice", 40), recipient("x", 20)]),
+        )
+        .unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![
+                ("alice".to_string(), coins(20, DENOM)),
+                ("treasury".to_string(), coins(10, DENOM)),
+            ]
+        );
+        assert_eq!(attribute(&response, "redirected"), Some("x:20"));
+
+        // the full amount counts towards the lifetime cap
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(80, DENOM)),
+            compound_msg(vec![recipient("alice", 80)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::LifetimeCapExceeded { .. }));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::FreezeRecipient {
+                address: "alice".to_string(),
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(10, DENOM)),
+            compound_msg(vec![recipient("alice", 10)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RecipientFrozen { .. }));
+    }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,4 +17,94 @@ pub enum ContractError {
 
     #[error("Duplicate recipient in list")]
     DuplicateRecipient {},
+
+    #[error("Recipient list is empty")]
+    EmptyRecipients {},
+
+    #[error("Total weight of recipients is zero")]
+    ZeroTotalWeight {},
+
+    #[error("Insufficient {denom} sent to pay the recipients")]
+    InsufficientFunds { denom: String },
+
+    #[error("Wrapper minted {got} tokens instead of {expected}")]
+    UnexpectedWrappedAmount { expected: Uint128, got: Uint128 },
+
+    #[error("Swap returned {got}, less than the minimum output {min}")]
+    SlippageExceeded { min: Uint128, got: Uint128 },
+
+    #[error("Unwrap adapter sent {got} coins instead of {expected}")]
+    UnexpectedUnwrappedAmount { expected: Uint128, got: Uint128 },
+
+    #[error("Vault left a {denom} shortfall of {shortfall}")]
+    VaultShortfall { denom: String, shortfall: Uint128 },
+
+    #[error("No failed {denom} transfers to reclaim")]
+    NothingToReclaim { denom: String },
+
+    #[error("Missing condition for recipient {recipient}")]
+    MissingCondition { recipient: String },
+
+    #[error("Duplicate condition for recipient {recipient}")]
+    DuplicateCondition { recipient: String },
+
+    #[error("Deferred recipients are not supported by this distribution")]
+    UnsupportedDeferred {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Gas reserve denom must differ from the distributed denom")]
+    InvalidGasReserveDenom {},
+
+    #[error("Too many denoms in one distribution (limit: {limit}, got: {got})")]
+    TooManyDenoms { limit: u32, got: u32 },
+
+    #[error("Distribution total is below the minimum (min: {min}, got: {got})")]
+    TotalBelowMinimum { min: Uint128, got: Uint128 },
+
+    #[error("No funds sent")]
+    NoFundsSent {},
+
+    #[error("Recipient amount must be greater than zero")]
+    ZeroAmount {},
+
+    #[error("Too many recipients in one distribution (max: {max}, got: {got})")]
+    TooManyRecipients { max: u32, got: u32 },
+
+    #[error("Dispute window is still open")]
+    DisputeWindowOpen {},
+
+    #[error("Dispute window has closed")]
+    DisputeWindowClosed {},
+
+    #[error("Invalid nonce (expected: {expected}, got: {got})")]
+    InvalidNonce { expected: u64, got: u64 },
+
+    #[error("Address {address} does not match the configured address format")]
+    InvalidAddressFormat { address: String },
+
+    #[error("Address {address} has not opted in")]
+    NotOptedIn { address: String },
+
+    #[error("Distribution was not approved by the approval hook")]
+    NotApproved {},
+
+    #[error("Recipient {recipient} would exceed the lifetime cap of {cap}")]
+    LifetimeCapExceeded { recipient: String, cap: Uint128 },
+
+    #[error("Address {address} is frozen")]
+    RecipientFrozen { address: String },
+
+    #[error("Unknown currency {currency}")]
+    UnknownCurrency { currency: String },
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Recipient {recipient} is denied by the policy contract")]
+    RecipientDenied { recipient: String },
+
+    #[error("This distribution does not accept funds")]
+    UnexpectedFunds {},
 }
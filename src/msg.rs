@@ -1,13 +1,71 @@
+use std::collections::BTreeMap;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use cosmwasm_std::{Uint128};
+use cosmwasm_std::{Binary, Coin, Decimal, Timestamp, Uint128};
 use cw20::Cw20ReceiveMsg;
 
 
 /// ## Description
 /// This structure stores the basic settings for creating a new contract.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    /// Address allowed to trigger distributions and update the owner
+    pub owner: String,
+    /// Contract notified with a summary after every native distribution
+    pub post_hook_contract: Option<String>,
+    /// Maximum number of distinct denoms a multi-asset distribution may send
+    pub max_denoms_per_tx: Option<u32>,
+    /// Minimum total amount a native distribution must send
+    pub min_total: Option<Uint128>,
+    /// Contract queried to allow or deny every recipient
+    pub policy_contract: Option<String>,
+    /// How recipients denied by the policy contract are handled
+    #[serde(default)]
+    pub deny_policy: DenyPolicy,
+    /// Address receiving the funds of failed best-effort transfers
+    pub treasury_fallback: Option<String>,
+    /// Maximum number of recipients per distribution, 100 if omitted and unbounded if null
+    #[serde(default = "default_max_recipients")]
+    pub max_recipients: Option<u32>,
+    /// Registry contract resolving chain-agnostic identifiers to local addresses
+    pub bridge_registry: Option<String>,
+    /// How much a distribution reports in its attributes and events
+    #[serde(default)]
+    pub event_verbosity: EventLevel,
+    /// Format recipient addresses must have
+    #[serde(default)]
+    pub address_format: AddressFormat,
+    /// Only pay recipients that opted in, deferring native amounts for the others until they do
+    #[serde(default)]
+    pub require_opt_in: bool,
+    /// Contract that must approve every distribution before it executes
+    pub approval_hook: Option<String>,
+    /// Maximum total amount of an asset one recipient may receive across all distributions
+    pub lifetime_cap: Option<Uint128>,
+    /// Vault contract covering the shortfall of distributions paid from the contract's balance
+    pub vault_contract: Option<String>,
+    /// Denom and rate of every logical settlement currency
+    #[serde(default)]
+    pub currencies: Vec<CurrencyRate>,
+    /// Adapter contract unwrapping received CW20 tokens into native coins
+    pub unwrap_adapter: Option<String>,
+    /// Address receiving the amounts of recipients whose address fails validation, instead of
+    /// skipping them or aborting
+    pub fallback_recipient: Option<String>,
+}
+
+/// ## Description
+/// Returns the maximum number of recipients used when [`InstantiateMsg`] omits it.
+pub(crate) fn default_max_recipients() -> Option<u32> {
+    Some(100)
+}
+
+/// ## Description
+/// Returns the atomicity used when [`ExecuteMsg::DistributeMultiAsset`] omits it.
+fn default_atomic() -> bool {
+    true
+}
 
 /// ## Description
 /// This structure describes the execute messages of the contract.
@@ -22,9 +80,406 @@ pub enum ExecuteMsg {
         denom: String,
         /// List of individual recipient addresses and amount
         recipients: Vec<Recipient>,
+        /// How recipients with an invalid address are handled
+        #[serde(default)]
+        failure_policy: FailurePolicy,
+        /// Keep distributing to the other recipients if a transfer fails, leaving the failed
+        /// amount in the contract
+        #[serde(default)]
+        best_effort: bool,
+        /// Denom of a gas-reserve coin attached alongside `denom`, refunded to the sender
+        gas_reserve_denom: Option<String>,
+        /// Decimals of `denom`, emits a decimal-formatted display amount per recipient if set
+        display_decimals: Option<u8>,
+        /// Next nonce of the sender, rejects the distribution if out of order or replayed
+        nonce: Option<u64>,
+    },
+    /// Distribute native SDK tokens with amounts interpolated linearly by recipient index
+    DistributeLinear {
+        /// Coin denom to send
+        denom: String,
+        /// List of recipient addresses, ordered from the highest to the lowest payout
+        recipients: Vec<String>,
+        /// Amount the first recipient will receive
+        top_amount: Uint128,
+        /// Amount the last recipient will receive
+        bottom_amount: Uint128,
+    },
+    /// Distribute several native SDK tokens at once, each recipient receiving a set of coins
+    DistributeMultiAsset {
+        /// List of individual recipient addresses and coins
+        recipients: Vec<MultiRecipient>,
+        /// Revert every transfer if one fails, otherwise each coin is transferred independently
+        #[serde(default = "default_atomic")]
+        atomic: bool,
+    },
+    /// Distribute native SDK tokens proportional to time-weighted balances
+    DistributeTimeWeighted {
+        /// Coin denom to send
+        denom: String,
+        /// List of individual addresses and their time-weighted balance
+        snapshots: Vec<(String, Uint128)>,
+        /// Round each payout down to a multiple of this amount, sweeping the remainder to the
+        /// last address
+        precision: Option<Uint128>,
+        /// Block height the snapshots were taken at, recorded for auditing only
+        snapshot_height: Option<u64>,
+    },
+    /// Distribute several native SDK tokens, paying each recipient in their preferred denom when
+    /// enough of it was sent and in the default denom otherwise
+    DistributePreferred {
+        /// Coin denom paying recipients without a preference or whose preference ran out
+        default_denom: String,
+        /// List of individual recipient addresses, amount and preferred denom
+        recipients: Vec<PreferredRecipient>,
+    },
+    /// Send a fixed reserve to one address, then split the remainder between the members of a
+    /// cw4 group by weight
+    DistributeGroupWithReserve {
+        /// Coin denom to send
+        denom: String,
+        /// Address of the cw4 group contract
+        group_addr: String,
+        /// Amount sent to `reserve_to` before splitting the remainder
+        reserve: Uint128,
+        /// Address receiving the reserve
+        reserve_to: String,
+        /// Round each member payout down to a multiple of this amount, sweeping the remainder to
+        /// the last member
+        precision: Option<Uint128>,
+    },
+    /// Wrap native SDK tokens into a CW20 through a wrapper contract and distribute the wrapped
+    /// tokens
+    DistributeWrapped {
+        /// Coin denom to wrap
+        denom: String,
+        /// Address of the wrapper contract, which is also the wrapped CW20 token
+        wrapper: String,
+        /// List of individual recipient addresses and amount
+        recipients: Vec<Recipient>,
+    },
+    /// Return the sender's failed best-effort native and multi-asset transfers of a denom
+    ReclaimFailed {
+        /// Coin denom of the failed transfers
+        denom: String,
+    },
+    /// Distribute native SDK tokens only to recipients whose condition is true, refunding the
+    /// rest to the sender
+    DistributeConditional {
+        /// Coin denom to send
+        denom: String,
+        /// List of individual recipient addresses and amount
+        recipients: Vec<Recipient>,
+        /// Condition flag for every recipient address
+        conditions: Vec<(String, bool)>,
+    },
+    /// Claim all native balances deferred to the sender
+    ClaimDeferred {},
+    /// Consent to receiving distributions
+    OptIn {},
+    /// Transfer ownership of the contract, only callable by the current owner
+    UpdateOwner {
+        /// Address of the new owner
+        new_owner: String,
+    },
+    /// Split the sent native SDK tokens equally between borrowers and repay their debt in a
+    /// lending contract, refunding whatever exceeds the debts
+    RepayDebts {
+        /// Coin denom to send
+        denom: String,
+        /// Address of the lending contract
+        lending_addr: String,
+        /// List of borrower addresses
+        borrowers: Vec<String>,
+    },
+    /// Distribute native SDK tokens where a sponsor matches each recipient's base amount
+    DistributeMatched {
+        /// Coin denom to send
+        denom: String,
+        /// List of individual recipient addresses and base amount
+        recipients: Vec<Recipient>,
+        /// Address of the sponsor funding the match
+        sponsor: String,
+        /// Match added on top of each base amount, in basis points of the base amount
+        match_bps: u16,
+    },
+    /// Distribute native SDK tokens so every recipient receives an equal value priced by an oracle
+    DistributeEqualValue {
+        /// Coin denom to send
+        denom: String,
+        /// List of recipient addresses
+        recipients: Vec<String>,
+        /// Address of the price oracle contract
+        oracle_addr: String,
+    },
+    /// Distribute native SDK tokens split by fixed shares of the sent amount
+    DistributeNativeByShares {
+        /// Coin denom to send
+        denom: String,
+        /// List of individual recipient addresses and weight
+        shares: Vec<Share>,
+    },
+    /// Distribute native SDK tokens equally between winners drawn from the candidates
+    DistributeRandom {
+        /// Coin denom to send
+        denom: String,
+        /// List of candidate addresses
+        candidates: Vec<String>,
+        /// Number of winners to draw
+        winners: u32,
+        /// Seed mixed with the block data to draw the winners
+        seed: Binary,
+    },
+    /// Distribute native SDK tokens equally between the recipients
+    DistributeNativeEqual {
+        /// Coin denom to send
+        denom: String,
+        /// List of recipient addresses
+        recipients: Vec<String>,
+    },
+    /// Distribute native SDK tokens to recipients identified by chain-agnostic identifiers
+    DistributeByIdentifier {
+        /// Coin denom to send
+        denom: String,
+        /// List of individual recipient identifiers and amount
+        recipients: Vec<IdentifiedRecipient>,
+        /// How identifiers the bridge registry cannot resolve are handled
+        #[serde(default)]
+        failure_policy: FailurePolicy,
+    },
+    /// Escrow native SDK tokens per recipient, claimable after a dispute window during which the
+    /// owner can claw them back
+    DistributeWithClawback {
+        /// Coin denom to send
+        denom: String,
+        /// List of individual recipient addresses and amount
+        recipients: Vec<Recipient>,
+        /// Length of the dispute window in seconds
+        dispute_window: u64,
+    },
+    /// Claim an escrow once its dispute window has passed, only callable by its recipient
+    ClaimAfterWindow {
+        /// Id of the escrow
+        id: u64,
+    },
+    /// Return an escrow to the owner during its dispute window, only callable by the owner
+    Clawback {
+        /// Id of the escrow
+        id: u64,
+    },
+    /// Distribute a share of the contract's free native balance by weight
+    DistributeBalanceShare {
+        /// Coin denom to send
+        denom: String,
+        /// List of individual recipient addresses and weight
+        recipients: Vec<WeightedRecipient>,
+        /// Share of the free balance to distribute, in basis points
+        share_bps: u16,
+    },
+    /// Validate and dedup a recipient list without moving funds, emitting the cleaned list
+    CleanRecipients {
+        /// List of individual recipient addresses and amount
+        recipients: Vec<Recipient>,
+    },
+    /// Distribute native SDK tokens proportional to each address's balance increase
+    DistributeByDiff {
+        /// Coin denom to send
+        reward_denom: String,
+        /// List of addresses and their balance at the start of the period
+        start_balances: Vec<(String, Uint128)>,
+        /// List of the same addresses, in the same order, and their balance at the end
+        end_balances: Vec<(String, Uint128)>,
+    },
+    /// Swap the contract's free balance of several native denoms into one denom through a swap
+    /// contract and send the proceeds to the owner
+    SweepDustToAsset {
+        /// Coin denoms whose free balance is swapped
+        assets: Vec<String>,
+        /// Coin denom the free balances are swapped into
+        target_denom: String,
+        /// Address of the swap contract
+        swap_contract: String,
+    },
+    /// Distribute native coins from the contract's free balance, withdrawing the shortfall from
+    /// the vault contract if the balance does not cover the recipients
+    DistributeFromBalance {
+        /// Coin denom to distribute
+        denom: String,
+        /// List of individual recipient addresses and amount
+        recipients: Vec<Recipient>,
+    },
+    /// Block an address from receiving distributions, only callable by the owner
+    FreezeRecipient {
+        /// Address to freeze
+        address: String,
+    },
+    /// Allow a frozen address to receive distributions again, only callable by the owner
+    UnfreezeRecipient {
+        /// Address to unfreeze
+        address: String,
+    },
+    /// Distribute native SDK tokens to recipients paid in a logical currency, converted to its
+    /// denom with the configured rate
+    DistributeInCurrency {
+        /// Logical currency the amounts are expressed in
+        currency: String,
+        /// List of recipient addresses and amount in currency units
+        recipients: Vec<(String, Uint128)>,
+    },
+    /// Distribute the sent native SDK tokens as a quadratic funding matching pool
+    DistributeQuadratic {
+        /// Coin denom to send
+        denom: String,
+        /// List of project addresses and the amounts contributed to each
+        contributions: Vec<(String, Vec<Uint128>)>,
+    },
+    /// Swap the sent native SDK tokens into `denom` through a swap contract and distribute the
+    /// proceeds by weight
+    DistributeSwapped {
+        /// Coin denom the sent coins are swapped into and distributed in
+        denom: String,
+        /// List of individual recipient addresses and weight
+        recipients: Vec<WeightedRecipient>,
+        /// Address of the swap contract
+        swap_contract: String,
+        /// Smallest swap output accepted, the distribution is reverted if the swap returns less
+        min_output: Uint128,
+    },
+    /// Distribute native SDK tokens, staking a share of every reward on the recipient's behalf
+    /// and sending the rest directly
+    DistributeWithCompound {
+        /// Coin denom to send
+        denom: String,
+        /// List of individual recipient addresses and amount
+        recipients: Vec<Recipient>,
+        /// Share of every amount staked, in basis points
+        compound_bps: u16,
+        /// Staking contract the compounded share is staked in
+        staking_contract: String,
     },
 }
 
+/// ## Description
+/// This structure describes the available query messages for the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Returns whether a distribution message would pass validation, without executing it
+    ValidateBatch {
+        /// Execute message of the distribution to validate
+        message: ExecuteMsg,
+    },
+    /// Returns the contract name, version and configuration
+    Config {},
+    /// Returns the amounts the recipients would receive if `target_total` were split by weight
+    NormalizeWeights {
+        /// List of individual recipient addresses and weight
+        recipients: Vec<WeightedRecipient>,
+        /// Total amount to split between the recipients
+        target_total: Uint128,
+        /// Round each amount down to a multiple of this amount, sweeping the remainder to the
+        /// last recipient
+        precision: Option<Uint128>,
+    },
+    /// Returns the total amount an address has received across all distributions
+    RecipientTotal {
+        /// Address of the recipient
+        address: String,
+        /// Native denom or CW20 contract address to filter by, all assets if not set
+        asset: Option<String>,
+    },
+    /// Returns the native balances deferred to a recipient
+    ClaimableBalance {
+        /// Address of the recipient
+        recipient: String,
+    },
+    /// Compares the amount needed to pay the recipients against the contract's free balance
+    Shortfall {
+        /// Native denom to check
+        asset: String,
+        /// List of individual recipient addresses and amount
+        recipients: Vec<Recipient>,
+    },
+    /// Returns every config parameter and its current value
+    AllParams {},
+    /// Returns the native amounts held by the contract on behalf of deferred and escrowed
+    /// recipients and senders of failed best-effort transfers
+    TotalEscrowed {
+        /// Native denom to filter by, all denoms if not set
+        denom: Option<String>,
+    },
+    /// Decodes a CW20 `Send` payload the same way the contract does when receiving tokens
+    DecodeHook {
+        /// Raw hook message
+        msg: Binary,
+    },
+    /// Estimates the bytes a distribution would persist
+    StorageEstimate {
+        /// Number of recipients of the distribution
+        recipients: u32,
+        /// Whether the distribution escrows every recipient's amount, as clawback distributions do
+        escrowed: bool,
+    },
+    /// Returns whether the contract's free balance covers an amount
+    CanAfford {
+        /// Native denom to check
+        asset: String,
+        /// Amount to pay out
+        amount: Uint128,
+    },
+    /// Returns the escrowed amounts becoming claimable within a horizon, in equal time buckets
+    ReleaseForecast {
+        /// Native denom to forecast
+        denom: String,
+        /// Length of the forecast in seconds from the current block time
+        horizon_seconds: u64,
+        /// Number of equal time buckets the horizon is divided into
+        buckets: u32,
+    },
+    /// Returns whether an address has opted in to receiving distributions
+    HasOptedIn {
+        /// Address to check
+        address: String,
+    },
+    /// Returns whether an address is frozen from receiving distributions
+    IsFrozen {
+        /// Address to check
+        address: String,
+    },
+    /// Returns the supported execute messages and their required fields
+    MessageSchema {},
+    /// Returns the recipients added, removed and changed between two recipient lists
+    DiffRecipients {
+        /// Previous list of recipient addresses and amount
+        old: Vec<Recipient>,
+        /// Updated list of recipient addresses and amount
+        new: Vec<Recipient>,
+    },
+    /// Suggests how to split a large distribution into calls that stay under a gas target
+    SuggestPageSize {
+        /// Total number of recipients
+        recipient_count: u32,
+        /// Gas each call should stay under
+        gas_target: u64,
+    },
+    /// Applies a sequence of hypothetical distributions from the contract's free balance and
+    /// returns which of them would fail
+    SimulateBatch {
+        /// Distributions to simulate, in order
+        operations: Vec<SimOp>,
+    },
+}
+
+/// ## Description
+/// This structure describes one hypothetical distribution of the [`QueryMsg::SimulateBatch`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimOp {
+    /// Native denom to distribute
+    pub denom: String,
+    /// List of individual recipient addresses and amount
+    pub recipients: Vec<Recipient>,
+}
+
 /// ## Description
 /// This structure stores the recipient structure of the distribution
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -33,6 +488,142 @@ pub struct Recipient {
     pub recipient: String,
     /// Amount of assets the individual recipient will receive
     pub amount: Uint128,
+    /// Record a claimable balance instead of sending the amount right away
+    #[serde(default)]
+    pub deferred: bool,
+}
+
+/// ## Description
+/// This structure stores the recipient structure of a share-based distribution
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Share {
+    /// Address of the individual recipient
+    pub recipient: String,
+    /// Weight of the individual recipient relative to the total weight
+    pub weight: u64,
+}
+
+/// ## Description
+/// This structure stores the recipient structure of a weighted distribution
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WeightedRecipient {
+    /// Address of the individual recipient
+    pub recipient: String,
+    /// Weight of the individual recipient relative to the total weight
+    pub weight: Uint128,
+    /// Smallest amount the recipient accepts, the recipient is skipped and their share
+    /// redistributed to the others if their computed amount is lower
+    #[serde(default)]
+    pub min_accept: Option<Uint128>,
+}
+
+/// ## Description
+/// This structure stores the conversion of a logical settlement currency to a native denom
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurrencyRate {
+    /// Name of the logical currency
+    pub currency: String,
+    /// Coin denom the currency settles in
+    pub denom: String,
+    /// Amount of `denom` per currency unit
+    pub rate: Decimal,
+}
+
+/// ## Description
+/// This structure stores a recipient identified by a chain-agnostic identifier (e.g. CAIP-10)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IdentifiedRecipient {
+    /// Chain-agnostic identifier of the individual recipient
+    pub id: String,
+    /// Amount of tokens the individual recipient will receive
+    pub amount: Uint128,
+}
+
+/// ## Description
+/// This structure stores the recipient structure of a multi-asset distribution
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MultiRecipient {
+    /// Address of the individual recipient
+    pub recipient: String,
+    /// Coins the individual recipient will receive
+    pub coins: Vec<Coin>,
+}
+
+/// ## Description
+/// This structure stores the recipient structure of a preferred denom distribution
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PreferredRecipient {
+    /// Address of the individual recipient
+    pub recipient: String,
+    /// Amount of assets the individual recipient will receive
+    pub amount: Uint128,
+    /// Coin denom the individual recipient prefers to be paid in
+    pub preferred_denom: Option<String>,
+}
+
+/// ## Description
+/// This enum describes the format recipient addresses are checked against before validation.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressFormat {
+    /// Lowercase bech32, e.g. `terra1...`
+    Bech32,
+    /// `0x` followed by 40 hex characters
+    Ethereum,
+    /// No format check beyond the chain's address validation
+    #[default]
+    Any,
+}
+
+/// ## Description
+/// This enum describes how much a distribution reports.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EventLevel {
+    /// Only the `action` attribute
+    Minimal,
+    /// The `action` attribute plus the asset, total amount and number of recipients
+    Summary,
+    /// The summary plus one event per recipient
+    #[default]
+    Full,
+}
+
+/// ## Description
+/// This enum describes how a distribution handles recipients denied by the policy contract.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DenyPolicy {
+    /// Reject the whole distribution if any recipient is denied
+    #[default]
+    Reject,
+    /// Drop denied recipients and distribute to the rest
+    Drop,
+}
+
+/// ## Description
+/// This enum describes how a distribution handles recipients whose address fails validation.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Reject the whole distribution if any recipient address is invalid
+    #[default]
+    AbortAll,
+    /// Drop recipients with an invalid address and distribute to the rest
+    SkipInvalid,
+}
+
+/// ## Description
+/// This enum describes what a CW20 distribution pays its recipients.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    /// Pay the received CW20 token
+    #[default]
+    SameToken,
+    /// Pay native coins of the given denom, unwrapped from the received token by the unwrap
+    /// adapter
+    Native(String),
 }
 
 /// ## Description
@@ -45,12 +636,476 @@ pub enum Cw20HookMsg {
         asset_token: String,
         /// List of individual recipient addresses and amount
         recipients: Vec<Recipient>,
+        /// How recipients with an invalid address are handled
+        #[serde(default)]
+        failure_policy: FailurePolicy,
+        /// Next nonce of the sender, rejects the distribution if out of order or replayed
+        nonce: Option<u64>,
+        /// What the recipients are paid, the received token or native coins unwrapped from it
+        #[serde(default)]
+        output: OutputKind,
+    },
+    DistributeCw20ByShares {
+        /// Address of CW20 token contract to send
+        asset_token: String,
+        /// List of individual recipient addresses and weight
+        shares: Vec<Share>,
+    },
+    DistributeCw20Equal {
+        /// Address of CW20 token contract to send
+        asset_token: String,
+        /// List of recipient addresses
+        recipients: Vec<String>,
+    },
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::ValidateBatch`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidateBatchResponse {
+    /// Whether the distribution would pass validation
+    pub valid: bool,
+    /// Error the distribution would fail with, none if it is valid
+    pub error: Option<String>,
+    /// Native coins the distribution must be sent with
+    pub required_funds: Vec<Coin>,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::NormalizeWeights`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NormalizeWeightsResponse {
+    /// List of individual recipient addresses and normalized amount
+    pub recipients: Vec<Recipient>,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::RecipientTotal`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RecipientTotalResponse {
+    /// Address of the recipient
+    pub address: String,
+    /// Total amount received per asset
+    pub totals: Vec<AssetAmount>,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::ClaimableBalance`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimableBalanceResponse {
+    /// Address of the recipient
+    pub recipient: String,
+    /// Coins the recipient can claim
+    pub amount: Vec<Coin>,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::Shortfall`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShortfallResponse {
+    /// Sum of the recipient amounts
+    pub required: Uint128,
+    /// Contract balance not reserved for deferred recipients or failed best-effort transfers
+    pub available: Uint128,
+    /// Amount missing to pay every recipient, zero if the balance suffices
+    pub shortfall: Uint128,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::AllParams`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllParamsResponse {
+    /// Config parameter name and its JSON-serialized value
+    pub params: BTreeMap<String, String>,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::Config`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    /// Contract name stored by cw2
+    pub contract_name: String,
+    /// Contract version stored by cw2
+    pub version: String,
+    /// Address allowed to trigger distributions
+    pub owner: Option<String>,
+    /// Contract notified with a summary after every native distribution
+    pub post_hook_contract: Option<String>,
+    /// Maximum number of distinct denoms a multi-asset distribution may send
+    pub max_denoms_per_tx: Option<u32>,
+    /// Minimum total amount a native distribution must send
+    pub min_total: Option<Uint128>,
+    /// Contract queried to allow or deny every recipient
+    pub policy_contract: Option<String>,
+    /// How recipients denied by the policy contract are handled
+    pub deny_policy: DenyPolicy,
+    /// Address receiving the funds of failed best-effort transfers
+    pub treasury_fallback: Option<String>,
+    /// Maximum number of recipients per distribution
+    pub max_recipients: Option<u32>,
+    /// Registry contract resolving chain-agnostic identifiers to local addresses
+    pub bridge_registry: Option<String>,
+    /// How much a distribution reports in its attributes and events
+    pub event_verbosity: EventLevel,
+    /// Format recipient addresses must have
+    pub address_format: AddressFormat,
+    /// Only pay recipients that opted in
+    pub require_opt_in: bool,
+    /// Contract that must approve every distribution before it executes
+    pub approval_hook: Option<String>,
+    /// Maximum total amount of an asset one recipient may receive across all distributions
+    pub lifetime_cap: Option<Uint128>,
+    /// Vault contract covering the shortfall of distributions paid from the contract's balance
+    pub vault_contract: Option<String>,
+    /// Denom and rate of every logical settlement currency
+    pub currencies: Vec<CurrencyRate>,
+    /// Adapter contract unwrapping received CW20 tokens into native coins
+    pub unwrap_adapter: Option<String>,
+    /// Address receiving the amounts of recipients whose address fails validation
+    pub fallback_recipient: Option<String>,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::TotalEscrowed`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalEscrowedResponse {
+    /// Escrowed amount per denom
+    pub escrowed: Vec<Coin>,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::SimulateBatch`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateBatchResponse {
+    /// Outcome of every operation, in order
+    pub results: Vec<SimOpResult>,
+    /// Free balance left per simulated denom after the successful operations
+    pub remaining: Vec<Coin>,
+}
+
+/// ## Description
+/// This structure describes the outcome of one simulated distribution.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimOpResult {
+    /// Error the distribution would fail with, none if it would succeed
+    pub error: Option<String>,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::IsFrozen`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsFrozenResponse {
+    pub frozen: bool,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::HasOptedIn`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HasOptedInResponse {
+    pub opted_in: bool,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::ReleaseForecast`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReleaseForecastResponse {
+    /// Amount becoming claimable per bucket, the first bucket includes escrows already claimable
+    pub buckets: Vec<ReleaseBucket>,
+}
+
+/// ## Description
+/// This structure stores the escrowed amount becoming claimable within a time interval.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReleaseBucket {
+    /// Start of the interval, inclusive
+    pub start: Timestamp,
+    /// End of the interval, exclusive
+    pub end: Timestamp,
+    /// Amount becoming claimable within the interval
+    pub amount: Uint128,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::CanAfford`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CanAffordResponse {
+    /// Whether the free balance covers the amount
+    pub can_afford: bool,
+    /// Free balance of the contract
+    pub available: Uint128,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::SuggestPageSize`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SuggestPageSizeResponse {
+    /// Recommended number of recipients per call
+    pub page_size: u32,
+    /// Number of calls needed to reach every recipient
+    pub pages: u32,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::StorageEstimate`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StorageEstimateResponse {
+    /// Approximate number of bytes persisted
+    pub bytes: u64,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::MessageSchema`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MessageSchemaResponse {
+    /// Supported execute messages
+    pub execute: Vec<MessageDescription>,
+}
+
+/// ## Description
+/// This structure describes one message of the [`QueryMsg::MessageSchema`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MessageDescription {
+    /// Name of the message as it appears in JSON
+    pub name: String,
+    /// Fields the message requires
+    pub required: Vec<String>,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::DiffRecipients`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DiffRecipientsResponse {
+    /// Recipients only in the updated list
+    pub added: Vec<Recipient>,
+    /// Recipients only in the previous list
+    pub removed: Vec<Recipient>,
+    /// Recipients in both lists with a different amount
+    pub changed: Vec<RecipientChange>,
+}
+
+/// ## Description
+/// This structure describes a recipient whose amount changed between two recipient lists.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RecipientChange {
+    /// Address of the recipient
+    pub recipient: String,
+    /// Amount in the previous list
+    pub old_amount: Uint128,
+    /// Amount in the updated list
+    pub new_amount: Uint128,
+}
+
+/// ## Description
+/// This structure describes the response of the [`QueryMsg::DecodeHook`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DecodeHookResponse {
+    /// Decoded hook message if parsing succeeded
+    pub msg: Option<Cw20HookMsg>,
+    /// Parse error if parsing failed
+    pub error: Option<String>,
+}
+
+/// ## Description
+/// This structure stores an amount of a native denom or CW20 asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetAmount {
+    /// Native denom or CW20 contract address
+    pub asset: String,
+    /// Amount of the asset
+    pub amount: Uint128,
+}
+
+/// ## Description
+/// This structure describes the message sent to the post-distribution hook contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PostHookMsg {
+    /// Summary of a completed native distribution
+    DistributionSummary {
+        /// Address that triggered the distribution
+        sender: String,
+        /// Coins distributed
+        amount: Vec<Coin>,
+        /// Number of recipients paid
+        num_recipients: u32,
+    },
+}
+
+/// ## Description
+/// This structure describes the lending contract queries used by the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LendingQueryMsg {
+    /// Returns the outstanding debt of a borrower
+    Debt { borrower: String },
+}
+
+/// ## Description
+/// This structure describes the response of the lending [`LendingQueryMsg::Debt`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DebtResponse {
+    pub amount: Uint128,
+}
+
+/// ## Description
+/// This structure describes the lending contract execute messages sent by the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LendingExecuteMsg {
+    /// Repays the debt of a borrower with the attached funds
+    RepayOnBehalf { borrower: String },
+}
+
+/// ## Description
+/// This structure describes the staking contract execute messages sent by the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StakingExecuteMsg {
+    /// Stakes the attached funds on behalf of a recipient
+    StakeFor { recipient: String },
+}
+
+/// ## Description
+/// This structure describes the subset of cw4 group queries used by the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw4QueryMsg {
+    /// Returns the members of the group
+    ListMembers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+/// ## Description
+/// This structure describes the response of the cw4 [`Cw4QueryMsg::ListMembers`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw4MemberListResponse {
+    pub members: Vec<Cw4Member>,
+}
+
+/// ## Description
+/// This structure stores a member of a cw4 group.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw4Member {
+    pub addr: String,
+    pub weight: u64,
+}
+
+/// ## Description
+/// This structure describes the execute message of a wrapper contract minting its CW20 token for
+/// the native coins sent to it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapperExecuteMsg {
+    /// Mints wrapped tokens for the sent coins to the sender, one token per coin
+    Wrap {},
+}
+
+/// ## Description
+/// This structure describes the hook message of an unwrap adapter sending native coins for the
+/// CW20 tokens sent to it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UnwrapHookMsg {
+    /// Sends `denom` coins for the received tokens to the sender, one coin per token
+    Unwrap { denom: String },
+}
+
+/// ## Description
+/// This structure describes the execute message of a swap contract swapping the native coins
+/// sent to it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapExecuteMsg {
+    /// Swaps the sent coins into `ask_denom` and sends the proceeds back to the sender
+    Swap { ask_denom: String },
+}
+
+/// ## Description
+/// This structure describes the execute message of a vault contract linked to the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultExecuteMsg {
+    /// Sends `amount` of `denom` to the sender
+    Withdraw { denom: String, amount: Uint128 },
+}
+
+/// ## Description
+/// This structure describes the approval hook queries used by the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalQueryMsg {
+    /// Returns whether a proposed distribution may execute
+    Approve {
+        /// Native denom or CW20 contract address to distribute
+        asset: String,
+        /// List of individual recipient addresses and amount
+        recipients: Vec<Recipient>,
     },
 }
 
+/// ## Description
+/// This structure describes the response of the approval hook [`ApprovalQueryMsg::Approve`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ApprovalResponse {
+    pub approved: bool,
+}
+
+/// ## Description
+/// This structure describes the recipient policy contract queries used by the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyQueryMsg {
+    /// Returns whether an address may receive funds
+    Check { address: String },
+}
+
+/// ## Description
+/// This structure describes the response of the policy [`PolicyQueryMsg::Check`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckResponse {
+    pub allowed: bool,
+}
+
+/// ## Description
+/// This structure describes the bridge registry queries used by the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryQueryMsg {
+    /// Returns the local address mapped to a chain-agnostic identifier
+    Resolve { id: String },
+}
+
+/// ## Description
+/// This structure describes the response of the registry [`RegistryQueryMsg::Resolve`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ResolveResponse {
+    /// Local address, none if the identifier is not registered
+    pub address: Option<String>,
+}
+
+/// ## Description
+/// This structure describes the price oracle queries used by the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleQueryMsg {
+    /// Returns the USD price of one unit of a denom
+    Price { denom: String },
+}
+
+/// ## Description
+/// This structure describes the response of the oracle [`OracleQueryMsg::Price`] query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceResponse {
+    pub price: Decimal,
+}
+
 /// ## Description
 /// A struct used for migrating contracts.
-/// Currently take no arguments for migrations.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// Owner to set, required to distribute from contracts instantiated without an owner
+    pub owner: Option<String>,
+}
 
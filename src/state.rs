@@ -0,0 +1,269 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Coin, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
+
+use crate::msg::{
+    AddressFormat, CurrencyRate, DenyPolicy, EventLevel, FailurePolicy, Recipient,
+    WeightedRecipient,
+};
+
+/// ## Description
+/// Stores the contract configuration.
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// ## Description
+/// This structure stores the contract configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Config {
+    /// Address allowed to trigger distributions, contracts migrated from before owners existed
+    /// have none until one is set on migration
+    #[serde(default)]
+    pub owner: Option<Addr>,
+    /// Contract notified with a summary after every native distribution
+    pub post_hook_contract: Option<Addr>,
+    /// Maximum number of distinct denoms a multi-asset distribution may send
+    pub max_denoms_per_tx: Option<u32>,
+    /// Minimum total amount a native distribution must send
+    pub min_total: Option<Uint128>,
+    /// Contract queried to allow or deny every recipient
+    pub policy_contract: Option<Addr>,
+    /// How recipients denied by the policy contract are handled
+    #[serde(default)]
+    pub deny_policy: DenyPolicy,
+    /// Address receiving the funds of failed best-effort transfers
+    pub treasury_fallback: Option<Addr>,
+    /// Maximum number of recipients per distribution
+    pub max_recipients: Option<u32>,
+    /// Registry contract resolving chain-agnostic identifiers to local addresses
+    pub bridge_registry: Option<Addr>,
+    /// How much a distribution reports in its attributes and events
+    #[serde(default)]
+    pub event_verbosity: EventLevel,
+    /// Format recipient addresses must have
+    #[serde(default)]
+    pub address_format: AddressFormat,
+    /// Only pay recipients that opted in
+    #[serde(default)]
+    pub require_opt_in: bool,
+    /// Contract that must approve every distribution before it executes
+    pub approval_hook: Option<Addr>,
+    /// Maximum total amount of an asset one recipient may receive across all distributions
+    pub lifetime_cap: Option<Uint128>,
+    /// Vault contract covering the shortfall of distributions paid from the contract's balance
+    pub vault_contract: Option<Addr>,
+    /// Denom and rate of every logical settlement currency
+    #[serde(default)]
+    pub currencies: Vec<CurrencyRate>,
+    /// Adapter contract unwrapping received CW20 tokens into native coins
+    pub unwrap_adapter: Option<Addr>,
+    /// Address receiving the amounts of recipients whose address fails validation
+    pub fallback_recipient: Option<Addr>,
+}
+
+/// ## Description
+/// Stores the total amount each recipient has received per asset (native denom or CW20 address).
+pub const RECIPIENT_TOTALS: Map<(&Addr, &str), Uint128> = Map::new("recipient_totals");
+
+/// ## Description
+/// Stores the wrapped distribution waiting for the reply of its wrapper contract.
+pub const PENDING_WRAP: Item<PendingWrap> = Item::new("pending_wrap");
+
+/// ## Description
+/// Reply id of the wrap submessage of a wrapped distribution.
+pub const WRAP_REPLY_ID: u64 = u64::MAX;
+
+/// ## Description
+/// This structure stores a wrapped distribution until its native coins are wrapped.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingWrap {
+    /// Address of the wrapper contract and wrapped CW20 token
+    pub wrapper: Addr,
+    /// Wrapped token balance of the contract before wrapping
+    pub balance_before: Uint128,
+    /// List of individual recipient addresses and amount
+    pub recipients: Vec<Recipient>,
+}
+
+/// ## Description
+/// Stores the dust sweep waiting for the reply of its last swap.
+pub const PENDING_SWAP: Item<PendingSwap> = Item::new("pending_swap");
+
+/// ## Description
+/// Reply id of the last swap submessage of a dust sweep.
+pub const SWAP_REPLY_ID: u64 = u64::MAX - 1;
+
+/// ## Description
+/// This structure stores a dust sweep until its swaps are done.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingSwap {
+    /// Address the proceeds are sent to
+    pub recipient: Addr,
+    /// Coin denom the dust is swapped into
+    pub target_denom: String,
+    /// Balance of the target denom of the contract before swapping
+    pub balance_before: Uint128,
+}
+
+/// ## Description
+/// Stores the distribution paid from the contract's balance waiting for the reply of the vault
+/// contract covering its shortfall.
+pub const PENDING_VAULT: Item<PendingVault> = Item::new("pending_vault");
+
+/// ## Description
+/// Reply id of the vault withdrawal of a distribution paid from the contract's balance.
+pub const VAULT_REPLY_ID: u64 = u64::MAX - 2;
+
+/// ## Description
+/// This structure stores a distribution paid from the contract's balance until the vault
+/// contract withdrew its shortfall.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingVault {
+    /// Address that started the distribution
+    pub sender: Addr,
+    /// Coin denom to distribute
+    pub denom: String,
+    /// List of individual recipient addresses and amount
+    pub recipients: Vec<Recipient>,
+}
+
+/// ## Description
+/// Stores the CW20 distribution waiting for the reply of the unwrap adapter.
+pub const PENDING_UNWRAP: Item<PendingUnwrap> = Item::new("pending_unwrap");
+
+/// ## Description
+/// Reply id of the unwrap submessage of a CW20 distribution paying native coins.
+pub const UNWRAP_REPLY_ID: u64 = u64::MAX - 3;
+
+/// ## Description
+/// This structure stores a CW20 distribution paying native coins until its tokens are unwrapped.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingUnwrap {
+    /// Address that sent the CW20 tokens
+    pub sender: Addr,
+    /// Coin denom the tokens are unwrapped into
+    pub denom: String,
+    /// Amount of tokens sent to the unwrap adapter
+    pub amount: Uint128,
+    /// Balance of the denom of the contract before unwrapping
+    pub balance_before: Uint128,
+    /// List of individual recipient addresses and amount
+    pub recipients: Vec<Recipient>,
+    /// How recipients with an invalid address are handled
+    pub failure_policy: FailurePolicy,
+}
+
+/// ## Description
+/// Stores the distribution waiting for the reply of the swap converting its coins.
+pub const PENDING_SWAP_DISTRIBUTION: Item<PendingSwapDistribution> =
+    Item::new("pending_swap_distribution");
+
+/// ## Description
+/// Reply id of the swap submessage of a swapped distribution.
+pub const SWAP_DISTRIBUTION_REPLY_ID: u64 = u64::MAX - 4;
+
+/// ## Description
+/// This structure stores a swapped distribution until its coins are swapped.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingSwapDistribution {
+    /// Address that started the distribution
+    pub sender: Addr,
+    /// Coin denom the sent coins are swapped into
+    pub denom: String,
+    /// Smallest swap output accepted
+    pub min_output: Uint128,
+    /// Balance of the denom of the contract before swapping
+    pub balance_before: Uint128,
+    /// List of individual recipient addresses and weight
+    pub recipients: Vec<WeightedRecipient>,
+}
+
+/// ## Description
+/// Stores the failed best-effort and multi-asset transfer amounts each sender may reclaim per
+/// denom.
+pub const RECLAIMABLE: Map<(&Addr, &str), Uint128> = Map::new("reclaimable");
+
+/// ## Description
+/// Stores the native amount each deferred recipient can claim per denom.
+pub const CLAIMABLE: Map<(&Addr, &str), Uint128> = Map::new("claimable");
+
+/// ## Description
+/// Stores the total native amount per denom held by the contract on behalf of deferred
+/// recipients and of senders with failed best-effort transfers.
+pub const RESERVED: Map<&str, Uint128> = Map::new("reserved");
+
+/// ## Description
+/// Stores the addresses that opted in to receiving distributions.
+pub const OPTED_IN: Map<&Addr, bool> = Map::new("opted_in");
+
+/// ## Description
+/// Stores the addresses the owner froze from receiving distributions.
+pub const FROZEN: Map<&Addr, bool> = Map::new("frozen");
+
+/// ## Description
+/// Stores the last nonce each sender used.
+pub const NONCES: Map<&Addr, u64> = Map::new("nonces");
+
+/// ## Description
+/// Stores the escrows of clawback distributions by id.
+pub const ESCROWS: Map<u64, Escrow> = Map::new("escrows");
+
+/// ## Description
+/// Stores the number of escrows created, used as the id of the next escrow.
+pub const ESCROW_COUNT: Item<u64> = Item::new("escrow_count");
+
+/// ## Description
+/// Stores the native amount each recipient holds in open escrows per denom.
+pub const ESCROWED: Map<(&Addr, &str), Uint128> = Map::new("escrowed");
+
+/// ## Description
+/// Stores the transfers of the latest best-effort native distribution. Reply ids index into
+/// `recipients`.
+pub const BEST_EFFORT_BATCH: Item<BestEffortBatch> = Item::new("best_effort_batch");
+
+/// ## Description
+/// This structure stores a recipient's escrowed amount of a clawback distribution.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Escrow {
+    /// Address of the recipient
+    pub recipient: Addr,
+    /// Coin denom escrowed
+    pub denom: String,
+    /// Amount escrowed
+    pub amount: Uint128,
+    /// Time the dispute window ends and the recipient may claim
+    pub release_at: Timestamp,
+}
+
+/// ## Description
+/// This structure stores the transfers of a non-atomic multi-asset distribution.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MultiAssetBatch {
+    /// Sender of the distribution, who may reclaim failed transfers
+    pub sender: Addr,
+    /// Recipient and coin of every transfer, reply ids minus `MULTI_ASSET_REPLY_OFFSET` index
+    /// into it
+    pub transfers: Vec<(Addr, Coin)>,
+}
+
+/// ## Description
+/// Stores the latest non-atomic multi-asset distribution.
+pub const MULTI_ASSET_BATCH: Item<MultiAssetBatch> = Item::new("multi_asset_batch");
+
+/// ## Description
+/// Offset added to the reply ids of non-atomic multi-asset transfers to tell them apart from
+/// best-effort transfers.
+pub const MULTI_ASSET_REPLY_OFFSET: u64 = 1 << 32;
+
+/// ## Description
+/// This structure stores the transfers of a best-effort native distribution.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BestEffortBatch {
+    /// Address of the distribution sender, who may reclaim failed transfers
+    pub sender: Addr,
+    /// Coin denom that was sent
+    pub denom: String,
+    /// List of individual recipient addresses and amount
+    pub recipients: Vec<Recipient>,
+}